@@ -0,0 +1,39 @@
+//! Crash-safe file replacement for the daemon's state files.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Write `contents` to `path` via a `.tmp` file and rename, creating the
+/// parent directory if needed, so a crash mid-write can't truncate `path`.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+    std::fs::write(&tmp_path, contents)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_creates_parent_and_replaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("data.json");
+        write_atomic(&path, b"one").unwrap();
+        write_atomic(&path, b"two").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two");
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+}
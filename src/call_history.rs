@@ -0,0 +1,231 @@
+//! N1MM+ / DXLog call history files.
+//!
+//! A call history file starts with a `!!Order!!` line naming its columns,
+//! followed by one comma-separated row per callsign. When a callsign is
+//! entered, the logger fills in the other columns (name, exchange) from the
+//! matching row. Lines starting with `#` are comments.
+//!
+//! Columns are configured as `Name` or `Name=placeholder`, where the
+//! placeholder is any note line template placeholder without braces (e.g.
+//! `Exch1=member_id`, `State=field:State`). `Call`, `Name`, `Exch1`,
+//! `CQZone` and `ITUZone` have defaults.
+
+use crate::csv_fetcher::Member;
+use crate::template::{Placeholder, member_value};
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+/// Columns used when none are configured.
+pub const DEFAULT_COLUMNS: &[&str] = &["Call", "Name", "Exch1"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Column {
+    name: String,
+    value: Placeholder,
+}
+
+/// Parsed call history column layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallHistory {
+    columns: Vec<Column>,
+}
+
+/// A member row, with the org it comes from.
+pub struct Entry<'a> {
+    pub member: &'a Member,
+    pub emoji: &'a str,
+    pub label: &'a str,
+}
+
+impl CallHistory {
+    pub fn parse(columns: &[String]) -> Result<Self> {
+        let mut parsed: Vec<Column> = Vec::new();
+        for spec in columns {
+            let (name, value) = match spec.split_once('=') {
+                Some((name, value)) => {
+                    let Some(value) = Placeholder::parse(value.trim()) else {
+                        bail!(
+                            "unknown value {:?} for column {}",
+                            value.trim(),
+                            name.trim()
+                        );
+                    };
+                    (name.trim(), value)
+                }
+                None => {
+                    let name = spec.trim();
+                    let value = match name {
+                        "Call" => Placeholder::Callsign,
+                        "Name" => Placeholder::Nickname,
+                        "Exch1" => Placeholder::MemberId,
+                        "CQZone" => Placeholder::CqZone,
+                        "ITUZone" => Placeholder::ItuZone,
+                        _ => bail!(
+                            "column {} has no default value; write {}=<placeholder>",
+                            name,
+                            name
+                        ),
+                    };
+                    (name, value)
+                }
+            };
+            if name.is_empty() || name.contains(',') {
+                bail!("invalid column name {:?}", name);
+            }
+            if parsed.iter().any(|c| c.name.eq_ignore_ascii_case(name)) {
+                bail!("duplicate column {}", name);
+            }
+            parsed.push(Column {
+                name: name.to_string(),
+                value,
+            });
+        }
+        if !parsed
+            .iter()
+            .any(|c| c.name == "Call" && c.value == Placeholder::Callsign)
+        {
+            bail!("call history needs a Call column");
+        }
+        Ok(Self { columns: parsed })
+    }
+
+    /// Roster columns the layout refers to with `field:...`.
+    pub fn roster_fields(&self) -> Vec<String> {
+        let mut fields: Vec<String> = Vec::new();
+        for column in &self.columns {
+            if let Placeholder::Field(name) = &column.value
+                && !fields.contains(name)
+            {
+                fields.push(name.clone());
+            }
+        }
+        fields
+    }
+
+    /// Render the file. `title` goes in a comment under the `!!Order!!`
+    /// line. A callsign in several entries gets the first entry's row, with
+    /// empty columns filled from later entries. Rows are in the order of
+    /// each callsign's first entry.
+    pub fn generate(&self, title: &str, entries: &[Entry]) -> String {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut row_index: HashMap<&str, usize> = HashMap::new();
+        for entry in entries {
+            let values = self.row(entry);
+            match row_index.get(entry.member.callsign.as_str()) {
+                Some(&i) => {
+                    for (existing, value) in rows[i].iter_mut().zip(values) {
+                        if existing.is_empty() {
+                            *existing = value;
+                        }
+                    }
+                }
+                None => {
+                    row_index.insert(&entry.member.callsign, rows.len());
+                    rows.push(values);
+                }
+            }
+        }
+
+        let names: Vec<&str> = self.columns.iter().map(|c| c.name.as_str()).collect();
+        let mut output = format!("!!Order!!,{}\n", names.join(","));
+        output.push_str(&format!("# {}\n", title));
+        output.push_str("# Do not edit manually - this file is auto-generated\n");
+        for row in rows {
+            output.push_str(&row.join(","));
+            output.push('\n');
+        }
+        output
+    }
+
+    fn row(&self, entry: &Entry) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|column| {
+                let value = match column.value {
+                    Placeholder::Emoji => Some(entry.emoji.to_string()),
+                    Placeholder::Label => Some(entry.label.to_string()),
+                    _ => member_value(entry.member, &column.value),
+                };
+                // No quoting in call history files
+                let value = value.unwrap_or_default().replace(',', " ");
+                value.split_whitespace().collect::<Vec<_>>().join(" ")
+            })
+            .collect()
+    }
+}
+
+impl Default for CallHistory {
+    fn default() -> Self {
+        let columns: Vec<String> = DEFAULT_COLUMNS.iter().map(|c| c.to_string()).collect();
+        Self::parse(&columns).expect("default columns are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(callsign: &str, member_id: &str, nickname: Option<&str>) -> Member {
+        let mut member = Member::new(callsign.to_string(), member_id.to_string());
+        member.nickname = nickname.map(str::to_string);
+        member
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        let columns = |specs: &[&str]| {
+            CallHistory::parse(&specs.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+        };
+        let history = columns(&["Call", "Name", "State=field:State"]).unwrap();
+        assert_eq!(history.roster_fields(), ["State"]);
+
+        for (specs, error) in [
+            (&["Call", "Loc1"][..], "no default value"),
+            (&["Call", "Exch1=number"][..], "unknown value"),
+            (&["Call", "Name", "name=nickname"][..], "duplicate column"),
+            (&["Name"][..], "needs a Call column"),
+        ] {
+            let err = columns(specs).unwrap_err().to_string();
+            assert!(err.contains(error), "{specs:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_generate_merges_entries() {
+        let history = CallHistory::parse(&[
+            "Call".to_string(),
+            "Name".to_string(),
+            "Exch1".to_string(),
+            "UserText=label".to_string(),
+        ])
+        .unwrap();
+        let w1aw = member("W1AW", "12", None);
+        let w1aw_cwops = member("W1AW", "345", Some("Bob, Jr"));
+        let k4mw = member("K4MW", "1", Some("Mike"));
+        let entries = [
+            Entry {
+                member: &k4mw,
+                emoji: "⚓",
+                label: "QRQ Crew",
+            },
+            Entry {
+                member: &w1aw,
+                emoji: "⚓",
+                label: "QRQ Crew",
+            },
+            Entry {
+                member: &w1aw_cwops,
+                emoji: "🎹",
+                label: "CWops",
+            },
+        ];
+
+        let output = history.generate("QRQ Crew call history", &entries);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "!!Order!!,Call,Name,Exch1,UserText");
+        assert_eq!(lines[1], "# QRQ Crew call history");
+        assert_eq!(lines[3], "K4MW,Mike,1,QRQ Crew");
+        // First entry wins; its empty name is filled from the later one
+        assert_eq!(lines[4], "W1AW,Bob Jr,12,QRQ Crew");
+    }
+}
@@ -0,0 +1,182 @@
+//! Circuit breaker for callbook lookups.
+//!
+//! When QRZ is down or the credentials stop working, every uncached
+//! callsign would otherwise go through a failing lookup (and a fresh login
+//! attempt) and log its own warning. The breaker opens after a run of
+//! consecutive failures, or immediately on an authentication error, and
+//! turns further lookups into no-ops until the cooldown has passed. After
+//! the cooldown a single trial lookup is let through: success closes the
+//! breaker, failure opens it for another cooldown.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default consecutive failures before the breaker opens.
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default time the breaker stays open before allowing a trial lookup.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed { consecutive_failures: u32 },
+    Open { since: Instant },
+    HalfOpen,
+}
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Mutex::new(State::Closed {
+                consecutive_failures: 0,
+            }),
+        }
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    /// True while the breaker is open and the cooldown hasn't elapsed.
+    /// Unlike [`allow`](Self::allow) this never changes state.
+    pub fn is_open(&self) -> bool {
+        match *self.state.lock().unwrap() {
+            State::Open { since } => since.elapsed() < self.cooldown,
+            State::HalfOpen => true,
+            State::Closed { .. } => false,
+        }
+    }
+
+    /// Whether a lookup may proceed. Once the cooldown has elapsed, the
+    /// first caller gets the trial lookup and everyone else is refused until
+    /// it reports back.
+    pub fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { since } if since.elapsed() >= self.cooldown => {
+                *state = State::HalfOpen;
+                true
+            }
+            State::Open { .. } | State::HalfOpen => false,
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = State::Closed {
+            consecutive_failures: 0,
+        };
+    }
+
+    /// Record a failed lookup. Returns `true` when this failure opened the
+    /// breaker, so the caller can log the transition exactly once.
+    pub fn record_failure(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed {
+                consecutive_failures,
+            } => {
+                let failures = consecutive_failures + 1;
+                if failures >= self.failure_threshold {
+                    *state = State::Open {
+                        since: Instant::now(),
+                    };
+                    true
+                } else {
+                    *state = State::Closed {
+                        consecutive_failures: failures,
+                    };
+                    false
+                }
+            }
+            State::HalfOpen => {
+                *state = State::Open {
+                    since: Instant::now(),
+                };
+                true
+            }
+            State::Open { .. } => false,
+        }
+    }
+
+    /// Open the breaker immediately (e.g. on an authentication error).
+    /// Returns `true` if it wasn't already open.
+    pub fn trip(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let was_open = matches!(*state, State::Open { .. });
+        *state = State::Open {
+            since: Instant::now(),
+        };
+        !was_open
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(DEFAULT_FAILURE_THRESHOLD, DEFAULT_COOLDOWN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+        assert!(breaker.allow());
+        assert!(breaker.record_failure());
+        assert!(breaker.is_open());
+        assert!(!breaker.allow());
+        // Further failures while open don't re-report the transition.
+        assert!(!breaker.record_failure());
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_success();
+        assert!(!breaker.record_failure());
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn test_trip_opens_immediately() {
+        let breaker = CircuitBreaker::new(5, Duration::from_secs(60));
+        assert!(breaker.trip());
+        assert!(!breaker.allow());
+        assert!(!breaker.trip());
+    }
+
+    #[test]
+    fn test_half_open_allows_single_trial_after_cooldown() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        assert!(breaker.record_failure());
+
+        // Cooldown elapsed: one trial goes through, the rest wait on it.
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+
+        // Trial failed: open again.
+        assert!(breaker.record_failure());
+
+        // Next trial succeeds and closes the breaker.
+        assert!(breaker.allow());
+        breaker.record_success();
+        assert!(!breaker.is_open());
+        assert!(breaker.allow());
+        assert!(breaker.allow());
+    }
+}
@@ -0,0 +1,193 @@
+//! One notes file for several orgs.
+//!
+//! A member of several orgs gets a single line listing each membership in
+//! org order, e.g. `W1AW Bob ⚓ QRQ Crew #12 · 🎹 CWops #345`, instead of a
+//! line per org.
+
+use crate::config::NicknamePriority;
+use crate::csv_fetcher::{Member, Source};
+use crate::notes_generator::Header;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+/// One org's members, with how it's shown on the line.
+pub struct OrgMembers<'a> {
+    pub emoji: &'a str,
+    pub label: &'a str,
+    pub members: &'a [Member],
+}
+
+pub struct CombinedNotes {
+    label: String,
+    url: String,
+    separator: String,
+    nickname_priority: NicknamePriority,
+    header: Header,
+}
+
+impl CombinedNotes {
+    pub fn new(label: String, url: Option<String>) -> Self {
+        Self {
+            label,
+            url: url.unwrap_or_default(),
+            separator: " · ".to_string(),
+            nickname_priority: NicknamePriority::OrgOrder,
+            header: Header::default(),
+        }
+    }
+
+    /// Text between memberships on a line (default: " · ").
+    pub fn with_separator(mut self, separator: String) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Which nickname wins when the orgs disagree: the first org's, or
+    /// names from QRZ or the roster first, then org order. Override names
+    /// always win.
+    pub fn with_nickname_priority(mut self, priority: NicknamePriority) -> Self {
+        self.nickname_priority = priority;
+        self
+    }
+
+    pub fn with_header(mut self, header: Header) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Render the file from `orgs`, in priority order. Lines are sorted by
+    /// callsign.
+    pub fn generate(&self, orgs: &[OrgMembers]) -> String {
+        self.generate_at(orgs, Utc::now())
+    }
+
+    /// Generate with `generated` as the header timestamp.
+    pub fn generate_at(&self, orgs: &[OrgMembers], generated: DateTime<Utc>) -> String {
+        // callsign -> (org, member), in org order
+        let mut by_call: BTreeMap<&str, Vec<(&OrgMembers, &Member)>> = BTreeMap::new();
+        for org in orgs {
+            for member in org.members {
+                by_call
+                    .entry(&member.callsign)
+                    .or_default()
+                    .push((org, member));
+            }
+        }
+
+        let mut output = self
+            .header
+            .render(&self.label, &self.url, by_call.len(), generated);
+        for (callsign, entries) in by_call {
+            let memberships: Vec<String> = entries
+                .iter()
+                .map(|(org, m)| format!("{} {} #{}", org.emoji, org.label, m.member_id))
+                .collect();
+            let mut line = callsign.to_string();
+            if let Some(nickname) = self.nickname(&entries) {
+                line.push(' ');
+                line.push_str(nickname);
+            }
+            line.push(' ');
+            line.push_str(&memberships.join(&self.separator));
+            output.push_str(&line);
+            output.push('\n');
+        }
+        output
+    }
+
+    fn nickname<'a>(&self, entries: &[(&OrgMembers, &'a Member)]) -> Option<&'a str> {
+        let preferred = match self.nickname_priority {
+            NicknamePriority::Qrz => Some(Source::Qrz),
+            NicknamePriority::Roster => Some(Source::Roster),
+            NicknamePriority::OrgOrder => None,
+        };
+        let rank = |m: &Member| match m.nickname_source {
+            Some(Source::Override) => 0,
+            source if preferred.is_some() && source == preferred => 1,
+            _ => 2,
+        };
+        entries
+            .iter()
+            .map(|(_, m)| m)
+            .filter(|m| m.nickname.is_some())
+            // min_by_key keeps the first of equal ranks, i.e. org order
+            .min_by_key(|m| rank(m))
+            .and_then(|m| m.nickname.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(callsign: &str, member_id: &str, nickname: Option<(&str, Source)>) -> Member {
+        let mut member = Member::new(callsign.to_string(), member_id.to_string());
+        member.nickname = nickname.map(|(n, _)| n.to_string());
+        member.nickname_source = nickname.map(|(_, s)| s);
+        member
+    }
+
+    #[test]
+    fn test_generate_merges_orgs() {
+        let qrq = [
+            member("W1AW", "12", Some(("Hiram", Source::Roster))),
+            member("K4MW", "1", None),
+        ];
+        let cwops = [member("W1AW", "345", Some(("Bob", Source::Qrz)))];
+        let orgs = [
+            OrgMembers {
+                emoji: "⚓",
+                label: "QRQ Crew",
+                members: &qrq,
+            },
+            OrgMembers {
+                emoji: "🎹",
+                label: "CWops",
+                members: &cwops,
+            },
+        ];
+
+        let notes = CombinedNotes::new("Club".to_string(), None);
+        let output = notes.generate(&orgs);
+        assert!(output.starts_with("# Club Callsign Notes for Ham2K PoLo\n"));
+        let lines: Vec<&str> = output
+            .lines()
+            .filter(|l| !l.starts_with('#') && !l.is_empty())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "K4MW ⚓ QRQ Crew #1",
+                "W1AW Hiram ⚓ QRQ Crew #12 · 🎹 CWops #345"
+            ]
+        );
+
+        let output = notes
+            .with_nickname_priority(NicknamePriority::Qrz)
+            .with_separator(" | ".to_string())
+            .generate(&orgs);
+        assert!(output.contains("W1AW Bob ⚓ QRQ Crew #12 | 🎹 CWops #345\n"));
+    }
+
+    #[test]
+    fn test_override_nickname_wins() {
+        let qrq = [member("W1AW", "12", Some(("Bob", Source::Qrz)))];
+        let cwops = [member("W1AW", "345", Some(("Hiram", Source::Override)))];
+        let orgs = [
+            OrgMembers {
+                emoji: "⚓",
+                label: "QRQ Crew",
+                members: &qrq,
+            },
+            OrgMembers {
+                emoji: "🎹",
+                label: "CWops",
+                members: &cwops,
+            },
+        ];
+        let output = CombinedNotes::new("Club".to_string(), None)
+            .with_nickname_priority(NicknamePriority::Qrz)
+            .generate(&orgs);
+        assert!(output.contains("W1AW Hiram ⚓"));
+    }
+}
@@ -1,5 +1,12 @@
-use anyhow::{Context, Result};
+use crate::call_history::CallHistory;
+use crate::formats;
+use crate::sort::SortOrder;
+use crate::template::{LineTemplate, validate_header};
+use anyhow::{Context, Result, bail};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fmt;
 use std::path::PathBuf;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -21,17 +28,359 @@ pub struct Organization {
     pub number_column_index: Option<usize>,
     #[serde(default)]
     pub skip_rows: usize,
+    /// Column with member names, by header name (CSV header or HTML `<th>`)
+    pub nickname_column: Option<String>,
+    /// Column index with member names (alternative to `nickname_column`)
+    pub nickname_column_index: Option<usize>,
+    /// Which name wins when both the roster and QRZ have one: "qrz"
+    /// (default) or "roster"
+    #[serde(default)]
+    pub nickname_priority: NicknamePriority,
     pub emoji: String,
     pub label: String,
-    pub output_file: String,
+    /// The PoLo notes file. Optional when `outputs` lists other files.
+    pub output_file: Option<String>,
     /// Optional per-organization GitHub settings (overrides global)
     pub github: Option<OrgGitHubConfig>,
+    /// Per-callsign overrides file (TOML or CSV), layered over the global one
+    pub overrides_file: Option<String>,
+    /// Also emit a line for each remapped member's former callsign
+    #[serde(default)]
+    pub former_calls: bool,
+    /// Marker between a former-call line and the current callsign (default: "→")
+    pub former_call_marker: Option<String>,
+    /// Stop emitting a former callsign this many days after the remap was
+    /// first seen (default: never)
+    pub former_call_max_age_days: Option<i64>,
+    /// Prefix note lines with the member's country flag (needs `dxcc_file`)
+    #[serde(default)]
+    pub flag_emoji: bool,
+    /// Which QRZ data this org uses (default: everything)
+    #[serde(default)]
+    pub enrichment: EnrichmentConfig,
+    /// Publish a callsign change report (from the remap history) at this
+    /// path alongside the notes. `.csv` for CSV, anything else is Markdown.
+    pub remap_report_file: Option<String>,
+    /// Publish remaps rejected by the identity check at this path, for a
+    /// human to confirm or override. `.csv` for CSV, anything else is
+    /// Markdown.
+    pub remap_review_file: Option<String>,
+    /// What to do with members whose license is expired or cancelled:
+    /// "keep" (default), "flag" (mark their note line) or "exclude".
+    #[serde(default)]
+    pub license_policy: LicensePolicy,
+    /// Marker for flagged lines, followed by the problem (default: "⚠️")
+    pub license_flag: Option<String>,
+    /// Publish members with license problems at this path. `.csv` for CSV,
+    /// anything else is Markdown.
+    pub license_report_file: Option<String>,
+    /// Publish roster callsigns QRZ reports as not found, with likely
+    /// corrections, at this path. `.csv` for CSV, anything else is Markdown.
+    pub typo_report_file: Option<String>,
+    /// Note line format, e.g. `{callsign} {emoji} [{nickname} ]{label}
+    /// #{member_id}`. See the `template` module for placeholders.
+    pub line_template: Option<String>,
+    /// Notes file header: URL, description, extra comment lines
+    #[serde(default)]
+    pub header: HeaderConfig,
+    /// Publish an N1MM+/DXLog call history file at this path
+    pub call_history_file: Option<String>,
+    /// Call history columns, in order, as `Name` or `Name=placeholder`
+    /// (default: Call, Name, Exch1 with the member id)
+    pub call_history_columns: Option<Vec<String>>,
+    /// Publish the final member list as JSON at this path (schema in
+    /// `docs/export-schema.md`)
+    pub json_export_file: Option<String>,
+    /// Publish the final member list as CSV at this path
+    pub csv_export_file: Option<String>,
+    /// Extra roster columns, by header name, to include in the exports
+    #[serde(default)]
+    pub export_fields: Vec<String>,
+    /// Line order for every output: "callsign" (default),
+    /// "callsign_natural", "member_id", "join_date" or "roster"
+    pub sort_by: Option<String>,
+    /// Roster column with the join date, for `sort_by = "join_date"`
+    pub join_date_column: Option<String>,
+    /// More files to publish from the member list, each in its own format
+    #[serde(default)]
+    pub outputs: Vec<OutputConfig>,
+}
+
+/// One file published from an org's member list. Options a format doesn't
+/// use are ignored.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OutputConfig {
+    /// "notes", "call_history", "json", "csv" or "scp"
+    pub format: String,
+    pub path: String,
+    /// notes: line template, instead of the org's `line_template`
+    pub line_template: Option<String>,
+    /// call_history: columns, instead of the org's `call_history_columns`
+    pub columns: Option<Vec<String>>,
+    /// json, csv: roster columns, instead of the org's `export_fields`
+    pub fields: Option<Vec<String>>,
+    /// Split the output by "call_area", "prefix" or "continent". `path`
+    /// then needs a `{shard}` placeholder.
+    pub shard_by: Option<String>,
+    /// With `shard_by`: where to publish the list of shard files. `.csv`
+    /// for CSV, anything else is Markdown.
+    pub index: Option<String>,
+}
+
+impl OutputConfig {
+    fn new(format: &str, path: &str) -> Self {
+        Self {
+            format: format.to_string(),
+            path: path.to_string(),
+            line_template: None,
+            columns: None,
+            fields: None,
+            shard_by: None,
+            index: None,
+        }
+    }
+}
+
+impl Organization {
+    /// The org's line template, parsed (the default if none is set).
+    pub fn line_template(&self) -> Result<LineTemplate> {
+        match &self.line_template {
+            Some(template) => LineTemplate::parse(template)
+                .with_context(|| format!("Invalid line_template for org {}", self.name)),
+            None => Ok(LineTemplate::default()),
+        }
+    }
+
+    /// The org's sort order, parsed.
+    pub fn sort_order(&self) -> Result<SortOrder> {
+        match &self.sort_by {
+            Some(sort_by) => SortOrder::parse(sort_by, self.join_date_column.as_deref())
+                .with_context(|| format!("Invalid sort_by for org {}", self.name)),
+            None => Ok(SortOrder::default()),
+        }
+    }
+
+    /// The org's call history columns, parsed.
+    pub fn call_history(&self) -> Result<CallHistory> {
+        parse_call_history(self.call_history_columns.as_deref())
+            .with_context(|| format!("Invalid call_history_columns for org {}", self.name))
+    }
+
+    /// Every file the org publishes from its member list: the ones set by
+    /// `output_file`, `call_history_file`, `json_export_file` and
+    /// `csv_export_file`, then the `outputs` list.
+    pub fn outputs(&self) -> Vec<OutputConfig> {
+        let legacy = [
+            ("notes", &self.output_file),
+            ("call_history", &self.call_history_file),
+            ("json", &self.json_export_file),
+            ("csv", &self.csv_export_file),
+        ];
+        legacy
+            .into_iter()
+            .filter_map(|(format, path)| path.as_deref().map(|p| OutputConfig::new(format, p)))
+            .chain(self.outputs.iter().cloned())
+            .collect()
+    }
+}
+
+fn parse_call_history(columns: Option<&[String]>) -> Result<CallHistory> {
+    match columns {
+        Some(columns) => CallHistory::parse(columns),
+        None => Ok(CallHistory::default()),
+    }
+}
+
+/// A call history file combining several orgs. A member of more than one
+/// org gets the row of the first org listed.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CombinedCallHistoryConfig {
+    pub file: String,
+    /// Orgs to include, by name, in priority order (default: every enabled
+    /// org, in config order)
+    pub organizations: Option<Vec<String>>,
+    /// Columns, as for `call_history_columns`
+    pub columns: Option<Vec<String>>,
+}
+
+impl CombinedCallHistoryConfig {
+    pub fn call_history(&self) -> Result<CallHistory> {
+        parse_call_history(self.columns.as_deref()).context("Invalid [call_history] columns")
+    }
+
+    /// Whether `org` is one of the orgs in the file.
+    pub fn includes(&self, org: &Organization) -> bool {
+        match &self.organizations {
+            Some(names) => names.contains(&org.name),
+            None => org.enabled,
+        }
+    }
+}
+
+/// A Super Check Partial file (one callsign per line) with the current
+/// callsigns of every enabled org.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ScpConfig {
+    pub file: String,
+    /// Comment lines at the top of the file
+    #[serde(default)]
+    pub header: Vec<String>,
+}
+
+/// A notes file merging several orgs into one line per callsign.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CombinedNotesConfig {
+    pub file: String,
+    /// Title label (default: "Club")
+    pub label: Option<String>,
+    /// Orgs to include, by name, in line order (default: every enabled org,
+    /// in config order)
+    pub organizations: Option<Vec<String>>,
+    /// Text between memberships (default: " · ")
+    pub separator: Option<String>,
+    /// Which nickname wins when orgs disagree: "org_order" (default, the
+    /// first org's), "qrz" or "roster". Override names always win.
+    #[serde(default = "default_combined_nickname_priority")]
+    pub nickname_priority: NicknamePriority,
+    #[serde(default)]
+    pub header: HeaderConfig,
+}
+
+fn default_combined_nickname_priority() -> NicknamePriority {
+    NicknamePriority::OrgOrder
+}
+
+/// Per-organization notes file header. The description and lines can use
+/// run metadata placeholders such as `{member_count}` and `{source}` (see
+/// the `template` module).
+#[derive(Debug, Deserialize, Clone)]
+pub struct HeaderConfig {
+    /// The org's website, written under the title
+    pub url: Option<String>,
+    /// One-line description, written under the URL
+    pub description: Option<String>,
+    /// Extra comment lines, written after the description
+    #[serde(default)]
+    pub lines: Vec<String>,
+    /// Write the `# Generated: ...` timestamp line
+    #[serde(default = "default_enabled")]
+    pub timestamp: bool,
+    /// Write the `# Do not edit manually` line
+    #[serde(default = "default_enabled")]
+    pub do_not_edit: bool,
+}
+
+impl HeaderConfig {
+    /// Check the description and lines for unknown placeholders.
+    pub fn validate(&self) -> Result<()> {
+        for text in self.description.iter().chain(&self.lines) {
+            validate_header(text).with_context(|| format!("in header text {:?}", text))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for HeaderConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            description: None,
+            lines: Vec::new(),
+            timestamp: true,
+            do_not_edit: true,
+        }
+    }
+}
+
+/// Per-organization QRZ enrichment policy. Only applies when `[qrz]` is
+/// enabled.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct EnrichmentConfig {
+    /// Look members up at all. Off skips QRZ entirely, cached data included.
+    #[serde(default = "default_enabled")]
+    pub lookups: bool,
+    /// Use QRZ first names as nicknames
+    #[serde(default = "default_enabled")]
+    pub nicknames: bool,
+    /// Replace roster callsigns with the operator's current callsign
+    #[serde(default = "default_enabled")]
+    pub remaps: bool,
+    /// Before remapping, check that the QRZ record's first name matches the
+    /// roster name (or the name cached for the roster call). Mismatches are
+    /// rejected and kept for review.
+    #[serde(default = "default_enabled")]
+    pub verify_remaps: bool,
+}
+
+impl EnrichmentConfig {
+    /// Whether there's anything to look up for. Lookups with both nicknames
+    /// and remaps turned off would only spend quota.
+    pub fn needs_lookups(&self) -> bool {
+        self.lookups && (self.nicknames || self.remaps)
+    }
+}
+
+impl Default for EnrichmentConfig {
+    fn default() -> Self {
+        Self {
+            lookups: true,
+            nicknames: true,
+            remaps: true,
+            verify_remaps: true,
+        }
+    }
+}
+
+impl fmt::Display for EnrichmentConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = match (self.needs_lookups(), self.nicknames, self.remaps) {
+            (false, _, _) => "no QRZ lookups",
+            (true, true, true) => "nicknames + remaps",
+            (true, true, false) => "nicknames only",
+            (true, false, _) => "remaps only",
+        };
+        f.write_str(summary)
+    }
+}
+
+/// What happens to members whose license is expired or cancelled.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LicensePolicy {
+    /// Check and report, but leave the notes alone
+    #[default]
+    Keep,
+    /// Mark their note lines
+    Flag,
+    /// Leave them out of every output
+    Exclude,
+}
+
+impl fmt::Display for LicensePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LicensePolicy::Keep => "keep",
+            LicensePolicy::Flag => "flag",
+            LicensePolicy::Exclude => "exclude",
+        })
+    }
 }
 
 fn default_source_type() -> String {
     "csv".to_string()
 }
 
+/// Which nickname source wins when more than one has a name.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NicknamePriority {
+    #[default]
+    Qrz,
+    Roster,
+    /// The first org's name, for `[combined_notes]` only
+    OrgOrder,
+}
+
 /// Per-organization GitHub config (all fields optional, falls back to global)
 #[derive(Debug, Deserialize, Clone)]
 pub struct OrgGitHubConfig {
@@ -51,6 +400,39 @@ pub struct Config {
     pub github: GitHubConfig,
     pub daemon: DaemonConfig,
     pub qrz: Option<QrzConfig>,
+    /// Per-callsign overrides file (TOML or CSV) applied to every org
+    pub overrides_file: Option<String>,
+    /// Country file (`cty.dat` or `cty.csv`) for DXCC entity resolution
+    pub dxcc_file: Option<String>,
+    /// Where the remap history is kept (default: remap_history.json)
+    pub remap_history_file: Option<String>,
+    /// Where the content hashes behind the `Generated:` timestamps are kept
+    /// (default: output_state.json)
+    pub output_state_file: Option<String>,
+    /// License status checks (QRZ `<expdate>` is always used when present)
+    pub license: Option<LicenseConfig>,
+    /// Call history file combining several orgs
+    pub call_history: Option<CombinedCallHistoryConfig>,
+    /// Super Check Partial file with every enabled org's callsigns
+    pub scp: Option<ScpConfig>,
+    /// Notes file with one line per callsign across several orgs
+    pub combined_notes: Option<CombinedNotesConfig>,
+}
+
+/// License data sources beyond QRZ, and when a license counts as expired.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LicenseConfig {
+    /// Check US callsigns QRZ has no expiry date for on callook.info
+    #[serde(default = "default_enabled")]
+    pub callook: bool,
+    /// Cache file for callook results (default: license_cache.json)
+    pub cache_path: Option<String>,
+    /// How long a callook result is reused (default: 30)
+    pub cache_ttl_days: Option<i64>,
+    /// Max callook lookups per cycle (default: 100)
+    pub max_lookups_per_cycle: Option<usize>,
+    /// Days past expiry before a license counts as expired (default: 0)
+    pub grace_days: Option<i64>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -59,10 +441,46 @@ pub struct QrzConfig {
     pub password: String,
     #[serde(default = "default_enabled")]
     pub enabled: bool,
-    /// Path to nickname cache file (default: nickname_cache.json)
+    /// Cache storage: "json" (default) or "sqlite"
+    #[serde(default)]
+    pub cache_backend: CacheBackend,
+    /// Path to nickname cache file (default: nickname_cache.json, or
+    /// nickname_cache.db for sqlite)
     pub cache_path: Option<String>,
+    /// JSON cache to import into an empty SQLite cache on startup
+    pub import_cache_path: Option<String>,
     /// Maximum concurrent QRZ lookups (default: 10)
     pub max_concurrent_lookups: Option<usize>,
+    /// Maximum uncached lookups per sync cycle across all orgs (default: unlimited)
+    pub max_lookups_per_cycle: Option<usize>,
+    /// Maximum lookups per UTC day, checked against QRZ's reported count
+    /// (default: unlimited)
+    pub max_lookups_per_day: Option<u64>,
+    /// Warn this many days before the QRZ subscription expires (default: 14)
+    pub subscription_warning_days: Option<i64>,
+    /// Consecutive lookup failures before the circuit breaker opens (default: 5)
+    pub breaker_failure_threshold: Option<u32>,
+    /// Seconds the circuit breaker stays open before a trial lookup (default: 1800)
+    pub breaker_cooldown_secs: Option<u64>,
+    /// Days a found entry stays fresh in the cache (default: 30)
+    pub found_ttl_days: Option<i64>,
+    /// Days a not-found entry stays fresh in the cache (default: 30)
+    pub not_found_ttl_days: Option<i64>,
+    /// Maximum random days added to each entry's TTL (default: 7)
+    pub ttl_jitter_days: Option<i64>,
+    /// Days past expiry a stale entry is kept before being pruned (default: 180)
+    pub max_stale_days: Option<i64>,
+    /// Maximum stale entries refreshed per org per cycle (default: 100)
+    pub max_stale_refreshes_per_cycle: Option<usize>,
+}
+
+/// Where the QRZ lookup cache is stored.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheBackend {
+    #[default]
+    Json,
+    Sqlite,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -82,6 +500,115 @@ pub struct DaemonConfig {
 }
 
 impl Config {
+    /// Error if `names`, from the `[table]` section, has an unknown or
+    /// disabled org. A disabled org never syncs, so the output would be
+    /// skipped every cycle.
+    fn check_org_names(&self, table: &str, names: Option<&[String]>) -> Result<()> {
+        for name in names.into_iter().flatten() {
+            match self.organizations.iter().find(|o| &o.name == name) {
+                None => bail!("[{}] lists unknown org {}", table, name),
+                Some(org) if !org.enabled => bail!("[{}] lists disabled org {}", table, name),
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Where an org (or, for `None`, a combined file) is committed, as
+    /// `owner/repo@branch`.
+    fn target(&self, org: Option<&Organization>) -> String {
+        let github = org.and_then(|o| o.github.as_ref());
+        format!(
+            "{}/{}@{}",
+            github
+                .and_then(|g| g.owner.as_deref())
+                .unwrap_or(&self.github.owner),
+            github
+                .and_then(|g| g.repo.as_deref())
+                .unwrap_or(&self.github.repo),
+            github
+                .and_then(|g| g.branch.as_deref())
+                .unwrap_or(&self.github.branch),
+        )
+    }
+
+    /// Error if two files go to the same path in the same repo and branch,
+    /// where the later one would silently replace the earlier in the batch
+    /// commit. Covers every enabled org's outputs and reports, and the
+    /// combined files (which go to the global repo).
+    fn check_output_paths(&self) -> Result<()> {
+        let mut published: HashMap<(String, String), String> = HashMap::new();
+        let mut publish = |target: &str, path: &str, by: String| match published
+            .entry((target.to_string(), path.to_string()))
+        {
+            Entry::Occupied(first) => bail!(
+                "{} in {} is published by both {} and {}",
+                path,
+                target,
+                first.get(),
+                by
+            ),
+            Entry::Vacant(slot) => {
+                slot.insert(by);
+                Ok(())
+            }
+        };
+
+        for org in self.organizations.iter().filter(|o| o.enabled) {
+            let target = self.target(Some(org));
+            for output in formats::for_org(org)? {
+                for path in output.published_paths() {
+                    publish(&target, &path, format!("org {}", org.name))?;
+                }
+            }
+            let reports = [
+                ("remap_report_file", &org.remap_report_file),
+                ("remap_review_file", &org.remap_review_file),
+                ("license_report_file", &org.license_report_file),
+                ("typo_report_file", &org.typo_report_file),
+            ];
+            for (field, path) in reports {
+                if let Some(path) = path {
+                    publish(&target, path, format!("org {} {}", org.name, field))?;
+                }
+            }
+        }
+
+        let global = self.target(None);
+        let combined = [
+            (
+                "[call_history]",
+                self.call_history.as_ref().map(|c| &c.file),
+            ),
+            (
+                "[combined_notes]",
+                self.combined_notes.as_ref().map(|c| &c.file),
+            ),
+            ("[scp]", self.scp.as_ref().map(|c| &c.file)),
+        ];
+        for (table, path) in combined {
+            if let Some(path) = path {
+                publish(&global, path, table.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Credentials in the config (GitHub tokens, QRZ password), for
+    /// registering with the log redaction.
+    pub fn secrets(&self) -> Vec<&str> {
+        let mut secrets = vec![self.github.token.as_str()];
+        secrets.extend(
+            self.organizations
+                .iter()
+                .filter_map(|o| o.github.as_ref()?.token.as_deref()),
+        );
+        if let Some(qrz) = &self.qrz {
+            secrets.push(qrz.password.as_str());
+        }
+        secrets
+    }
+
     pub fn load(path: Option<PathBuf>) -> Result<Self> {
         let config_path = path.unwrap_or_else(|| PathBuf::from("config.toml"));
 
@@ -125,6 +652,32 @@ impl Config {
             }
         }
 
+        // Fail at startup on a bad template rather than on every sync
+        for org in &config.organizations {
+            formats::for_org(org)?;
+            if org.nickname_priority == NicknamePriority::OrgOrder {
+                bail!(
+                    "Invalid nickname_priority for org {}: \"org_order\" only applies to [combined_notes]",
+                    org.name
+                );
+            }
+            org.header
+                .validate()
+                .with_context(|| format!("Invalid header for org {}", org.name))?;
+        }
+        if let Some(combined) = &config.call_history {
+            combined.call_history()?;
+            config.check_org_names("call_history", combined.organizations.as_deref())?;
+        }
+        if let Some(combined) = &config.combined_notes {
+            combined
+                .header
+                .validate()
+                .context("Invalid [combined_notes] header")?;
+            config.check_org_names("combined_notes", combined.organizations.as_deref())?;
+        }
+        config.check_output_paths()?;
+
         // Handle ${VAR} placeholder in QRZ credentials
         if let Some(ref mut qrz) = config.qrz {
             if qrz.username.starts_with("${") && qrz.username.ends_with("}") {
@@ -242,6 +795,59 @@ run_once = true
         assert!(config.organizations[0].enabled);
         // skip_rows should default to 0
         assert_eq!(config.organizations[0].skip_rows, 0);
+        // full enrichment by default
+        assert_eq!(
+            config.organizations[0].enrichment,
+            EnrichmentConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_config_enrichment_policy() {
+        let config_content = r#"
+[[organizations]]
+name = "no_remap"
+roster_url = "https://example.com/a.csv"
+emoji = "⚓"
+label = "A"
+output_file = "a.txt"
+
+[organizations.enrichment]
+remaps = false
+
+[[organizations]]
+name = "no_lookups"
+roster_url = "https://example.com/b.csv"
+emoji = "🎹"
+label = "B"
+output_file = "b.txt"
+enrichment = { lookups = false }
+
+[github]
+token = "test_token"
+owner = "testowner"
+repo = "testrepo"
+branch = "main"
+commit_author_name = "Test Bot"
+commit_author_email = "test@example.com"
+
+[daemon]
+sync_interval_secs = 3600
+run_once = true
+"#;
+
+        let mut temp_file = Builder::new().suffix(".toml").tempfile().unwrap();
+        temp_file.write_all(config_content.as_bytes()).unwrap();
+
+        let config = Config::load(Some(temp_file.path().to_path_buf())).unwrap();
+
+        let no_remap = config.organizations[0].enrichment;
+        assert!(no_remap.nicknames && !no_remap.remaps);
+        assert_eq!(no_remap.to_string(), "nicknames only");
+
+        let no_lookups = config.organizations[1].enrichment;
+        assert!(!no_lookups.needs_lookups());
+        assert_eq!(no_lookups.to_string(), "no QRZ lookups");
     }
 
     #[test]
@@ -324,6 +930,11 @@ run_once = true
 username = "testuser"
 password = "testpass"
 enabled = true
+max_lookups_per_cycle = 500
+max_lookups_per_day = 5000
+found_ttl_days = 60
+not_found_ttl_days = 7
+cache_backend = "sqlite"
 "#;
 
         let mut temp_file = Builder::new().suffix(".toml").tempfile().unwrap();
@@ -336,6 +947,13 @@ enabled = true
         assert_eq!(qrz.username, "testuser");
         assert_eq!(qrz.password, "testpass");
         assert!(qrz.enabled);
+        assert_eq!(qrz.max_lookups_per_cycle, Some(500));
+        assert_eq!(qrz.max_lookups_per_day, Some(5000));
+        assert!(qrz.subscription_warning_days.is_none());
+        assert_eq!(qrz.found_ttl_days, Some(60));
+        assert_eq!(qrz.not_found_ttl_days, Some(7));
+        assert!(qrz.ttl_jitter_days.is_none());
+        assert_eq!(qrz.cache_backend, CacheBackend::Sqlite);
     }
 
     #[test]
@@ -371,4 +989,245 @@ run_once = true
         // QRZ should be None when not configured
         assert!(config.qrz.is_none());
     }
+
+    #[test]
+    fn test_config_rejects_bad_line_template() {
+        let config_content = r#"
+[[organizations]]
+name = "test"
+roster_url = "https://example.com/test.csv"
+emoji = "🔥"
+label = "Test"
+output_file = "test.txt"
+line_template = "{callsign} {nick}"
+
+[github]
+token = "test_token"
+owner = "testowner"
+repo = "testrepo"
+branch = "main"
+commit_author_name = "Test Bot"
+commit_author_email = "test@example.com"
+
+[daemon]
+sync_interval_secs = 3600
+run_once = true
+"#;
+
+        let mut temp_file = Builder::new().suffix(".toml").tempfile().unwrap();
+        temp_file.write_all(config_content.as_bytes()).unwrap();
+
+        let err = Config::load(Some(temp_file.path().to_path_buf())).unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("Invalid line_template for org test"));
+        assert!(message.contains("unknown placeholder {nick}"));
+    }
+
+    #[test]
+    fn test_config_header() {
+        let config_content = r#"
+[[organizations]]
+name = "test"
+roster_url = "https://example.com/test.csv"
+emoji = "🔥"
+label = "Test"
+output_file = "test.txt"
+
+[organizations.header]
+url = "https://example.com"
+lines = ["{member_count} members from {source}"]
+timestamp = false
+
+[github]
+token = "test_token"
+owner = "testowner"
+repo = "testrepo"
+branch = "main"
+commit_author_name = "Test Bot"
+commit_author_email = "test@example.com"
+
+[daemon]
+sync_interval_secs = 3600
+run_once = true
+"#;
+
+        let mut temp_file = Builder::new().suffix(".toml").tempfile().unwrap();
+        temp_file.write_all(config_content.as_bytes()).unwrap();
+
+        let config = Config::load(Some(temp_file.path().to_path_buf())).unwrap();
+        let header = &config.organizations[0].header;
+        assert_eq!(header.url.as_deref(), Some("https://example.com"));
+        assert_eq!(header.lines, ["{member_count} members from {source}"]);
+        assert!(!header.timestamp);
+        assert!(header.do_not_edit);
+
+        let bad = HeaderConfig {
+            description: Some("{members} members".to_string()),
+            ..HeaderConfig::default()
+        };
+        let message = format!("{:#}", bad.validate().unwrap_err());
+        assert!(message.contains("unknown placeholder {members}"));
+    }
+
+    #[test]
+    fn test_combined_call_history_includes() {
+        let config_content = r#"
+[[organizations]]
+name = "a"
+roster_url = "https://example.com/a.csv"
+emoji = "🔥"
+label = "A"
+output_file = "a.txt"
+
+[[organizations]]
+name = "b"
+enabled = false
+roster_url = "https://example.com/b.csv"
+emoji = "🔥"
+label = "B"
+output_file = "b.txt"
+
+[call_history]
+file = "all.txt"
+columns = ["Call", "State=field:State"]
+
+[github]
+token = "test_token"
+owner = "testowner"
+repo = "testrepo"
+branch = "main"
+commit_author_name = "Test Bot"
+commit_author_email = "test@example.com"
+
+[daemon]
+sync_interval_secs = 3600
+run_once = true
+"#;
+
+        let mut temp_file = Builder::new().suffix(".toml").tempfile().unwrap();
+        temp_file.write_all(config_content.as_bytes()).unwrap();
+
+        let config = Config::load(Some(temp_file.path().to_path_buf())).unwrap();
+        let combined = config.call_history.as_ref().unwrap();
+        assert!(combined.includes(&config.organizations[0]));
+        assert!(!combined.includes(&config.organizations[1]));
+        assert_eq!(combined.call_history().unwrap().roster_fields(), ["State"]);
+    }
+
+    /// Load `org` (extra `[[organizations]]` keys) and `extra` (more tables)
+    /// on top of a minimal config.
+    fn load_with(org: &str, extra: &str) -> Result<Config> {
+        let config_content = format!(
+            r#"
+[[organizations]]
+name = "test"
+roster_url = "https://example.com/test.csv"
+emoji = "🔥"
+label = "Test"
+output_file = "test.txt"
+{org}
+
+[github]
+token = "test_token"
+owner = "testowner"
+repo = "testrepo"
+branch = "main"
+commit_author_name = "Test Bot"
+commit_author_email = "test@example.com"
+
+[daemon]
+sync_interval_secs = 3600
+run_once = true
+
+{extra}
+"#
+        );
+        let mut temp_file = Builder::new().suffix(".toml").tempfile().unwrap();
+        temp_file.write_all(config_content.as_bytes()).unwrap();
+        Config::load(Some(temp_file.path().to_path_buf()))
+    }
+
+    #[test]
+    fn test_license_policy() {
+        let config = load_with(r#"license_policy = "exclude""#, "").unwrap();
+        assert_eq!(
+            config.organizations[0].license_policy,
+            LicensePolicy::Exclude
+        );
+        assert_eq!(
+            load_with("", "").unwrap().organizations[0].license_policy,
+            LicensePolicy::Keep
+        );
+        assert!(load_with(r#"license_policy = "flagged""#, "").is_err());
+    }
+
+    #[test]
+    fn test_nickname_priority() {
+        let config = load_with(r#"nickname_priority = "roster""#, "").unwrap();
+        assert_eq!(
+            config.organizations[0].nickname_priority,
+            NicknamePriority::Roster
+        );
+        assert!(load_with(r#"nickname_priority = "Roster ""#, "").is_err());
+    }
+
+    #[test]
+    fn test_cache_backend_rejects_unknown() {
+        let qrz = |backend: &str| {
+            format!("[qrz]\nusername = \"u\"\npassword = \"p\"\ncache_backend = \"{backend}\"")
+        };
+        // Variants match case-insensitively; anything else is an error
+        let config = load_with("", &qrz("SQLite")).unwrap();
+        assert_eq!(config.qrz.unwrap().cache_backend, CacheBackend::Sqlite);
+        let message = format!("{:#}", load_with("", &qrz("sqlit")).unwrap_err());
+        assert!(message.contains("sqlit"));
+    }
+
+    #[test]
+    fn test_combined_nickname_priority() {
+        let combined = |priority: &str| {
+            format!("[combined_notes]\nfile = \"all.txt\"\nnickname_priority = \"{priority}\"")
+        };
+        let config = load_with("", &combined("qrz")).unwrap();
+        assert_eq!(
+            config.combined_notes.unwrap().nickname_priority,
+            NicknamePriority::Qrz
+        );
+        assert!(load_with("", &combined("org-order")).is_err());
+        // Only meaningful across orgs
+        assert!(load_with(r#"nickname_priority = "org_order""#, "").is_err());
+    }
+
+    #[test]
+    fn test_combined_outputs_reject_disabled_orgs() {
+        let table = "[combined_notes]\nfile = \"all.txt\"\norganizations = [\"test\"]";
+        assert!(load_with("", table).is_ok());
+        let message = format!("{:#}", load_with("enabled = false", table).unwrap_err());
+        assert!(message.contains("disabled org test"));
+    }
+
+    #[test]
+    fn test_output_paths_collide_across_config() {
+        let error = |org: &str, extra: &str| format!("{:#}", load_with(org, extra).unwrap_err());
+
+        let same_org = "[[organizations.outputs]]\nformat = \"scp\"\npath = \"test.txt\"";
+        assert!(error(same_org, "").contains("test.txt in testowner/testrepo@main"));
+
+        let report = "remap_report_file = \"test.txt\"";
+        assert!(error(report, "").contains("org test and org test remap_report_file"));
+
+        assert!(error("", "[scp]\nfile = \"test.txt\"").contains("org test and [scp]"));
+
+        let other = |keys: &str| {
+            format!(
+                "[[organizations]]\nname = \"other\"\nroster_url = \"https://example.com/o.csv\"\n\
+                 emoji = \"🔑\"\nlabel = \"Other\"\noutput_file = \"test.txt\"\n{}",
+                keys
+            )
+        };
+        assert!(error("", &other("")).contains("by both org test and org other"));
+        // Another repo, or a disabled org, doesn't collide
+        assert!(load_with("", &other("[organizations.github]\nrepo = \"otherrepo\"")).is_ok());
+        assert!(load_with("", &other("enabled = false")).is_ok());
+    }
 }
@@ -1,15 +1,90 @@
+use crate::dxcc::DxccEntity;
+use crate::license::{License, LicenseProblem};
+use crate::names::clean_name;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use regex::Regex;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::time::Duration;
 use tracing::{debug, warn};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Member {
     pub callsign: String,
     pub member_id: String,
     pub nickname: Option<String>,
+    /// Callsign as it appeared in the roster, before any remap to the
+    /// operator's current callsign.
+    pub roster_callsign: String,
+    /// Name from the roster's nickname column, before QRZ enrichment.
+    pub roster_nickname: Option<String>,
+    /// When the remap from `roster_callsign` was first seen, if known.
+    pub remapped_since: Option<DateTime<Utc>>,
+    /// DXCC entity of the current callsign, when a country file is loaded.
+    pub dxcc: Option<DxccEntity>,
+    /// License data for the current callsign, when a provider has it.
+    pub license: Option<License>,
+    /// Set by the license check when the license is expired or cancelled.
+    pub license_problem: Option<LicenseProblem>,
+    /// Extra roster columns requested by the line template, keyed by the
+    /// configured header name.
+    pub fields: HashMap<String, String>,
+    /// Where `nickname` came from.
+    pub nickname_source: Option<Source>,
+    /// Where the remap to `callsign` came from, when remapped.
+    pub remap_source: Option<Source>,
+}
+
+/// Where a member's nickname or current callsign came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Roster,
+    Qrz,
+    Override,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Roster => "roster",
+            Source::Qrz => "qrz",
+            Source::Override => "override",
+        }
+    }
+}
+
+impl Member {
+    pub fn new(callsign: String, member_id: String) -> Self {
+        Self {
+            roster_callsign: callsign.clone(),
+            callsign,
+            member_id,
+            nickname: None,
+            roster_nickname: None,
+            remapped_since: None,
+            dxcc: None,
+            license: None,
+            license_problem: None,
+            fields: HashMap::new(),
+            nickname_source: None,
+            remap_source: None,
+        }
+    }
+
+    /// True when the callsign was rewritten from the roster's value.
+    pub fn is_remapped(&self) -> bool {
+        !self.callsign.eq_ignore_ascii_case(&self.roster_callsign)
+    }
+}
+
+/// A roster column, by header name (case-insensitive) or 0-based index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnRef {
+    Name(String),
+    Index(usize),
 }
 
 pub struct CsvFetcher {
@@ -17,6 +92,8 @@ pub struct CsvFetcher {
     url: String,
     callsign_column: String,
     number_column: String,
+    nickname_column: Option<ColumnRef>,
+    extra_columns: Vec<String>,
     skip_rows: usize,
     callsign_regex: Regex,
 }
@@ -38,14 +115,31 @@ impl CsvFetcher {
             url,
             callsign_column,
             number_column,
+            nickname_column: None,
+            extra_columns: Vec::new(),
             skip_rows,
             callsign_regex: Regex::new(r"^[A-Z]{1,2}\d[A-Z]{1,4}$").unwrap(),
         }
     }
 
+    /// Read member names from this column, cleaned up like QRZ names.
+    pub fn with_nickname_column(mut self, column: Option<ColumnRef>) -> Self {
+        self.nickname_column = column;
+        self
+    }
+
+    /// Also read these columns (by header name) into `Member::fields`.
+    pub fn with_extra_columns(mut self, columns: Vec<String>) -> Self {
+        self.extra_columns = columns;
+        self
+    }
+
     pub async fn fetch_members(&self) -> Result<Vec<Member>> {
         let csv_data = self.fetch_with_retry(3).await?;
+        self.parse_csv(&csv_data)
+    }
 
+    fn parse_csv(&self, csv_data: &str) -> Result<Vec<Member>> {
         let mut reader = csv::ReaderBuilder::new()
             .has_headers(false)
             .from_reader(csv_data.as_bytes());
@@ -83,9 +177,28 @@ impl CsvFetcher {
                 )
             })?;
 
+        let nickname_col = match &self.nickname_column {
+            Some(ColumnRef::Name(name)) => Some(
+                self.find_column_by_name(&headers, name)
+                    .with_context(|| format!("Could not find nickname column '{}' in CSV", name))?,
+            ),
+            Some(ColumnRef::Index(index)) => Some(*index),
+            None => None,
+        };
+
+        let extra_cols = self
+            .extra_columns
+            .iter()
+            .map(|name| {
+                self.find_column_by_name(&headers, name)
+                    .map(|col| (name.clone(), col))
+                    .with_context(|| format!("Could not find column '{}' in CSV", name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
         debug!(
-            "Using column {} for callsigns, column {} for numbers",
-            callsign_col, number_col
+            "Using column {} for callsigns, column {} for numbers, column {:?} for nicknames",
+            callsign_col, number_col, nickname_col
         );
 
         let mut seen: HashSet<String> = HashSet::new();
@@ -136,11 +249,20 @@ impl CsvFetcher {
                         };
 
                         seen.insert(callsign.clone());
-                        members.push(Member {
-                            callsign,
-                            member_id,
-                            nickname: None,
-                        });
+                        let mut member = Member::new(callsign, member_id);
+                        member.roster_nickname = nickname_col
+                            .and_then(|col| record.get(col))
+                            .and_then(clean_name);
+                        member.nickname = member.roster_nickname.clone();
+                        member.nickname_source = member.nickname.as_ref().map(|_| Source::Roster);
+                        for (name, col) in &extra_cols {
+                            if let Some(value) = record.get(*col).map(str::trim)
+                                && !value.is_empty()
+                            {
+                                member.fields.insert(name.clone(), value.to_string());
+                            }
+                        }
+                        members.push(member);
                     }
                 }
                 Err(e) => {
@@ -149,9 +271,8 @@ impl CsvFetcher {
             }
         }
 
-        // Sort alphabetically
-        members.sort_by(|a, b| a.callsign.cmp(&b.callsign));
-
+        // Roster order: outputs sort through `SortOrder`, and `roster` order
+        // needs the sheet's own
         Ok(members)
     }
 
@@ -215,6 +336,7 @@ impl CsvFetcher {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::SortOrder;
 
     fn test_fetcher() -> CsvFetcher {
         CsvFetcher::new(
@@ -263,6 +385,65 @@ mod tests {
         assert_eq!(fetcher.find_column_by_name(&headers, "QC #"), None);
     }
 
+    #[test]
+    fn test_parse_csv_with_nickname_column() {
+        let csv = "Call,Name,Number\nW6JSV,JAMES S,10\nK4MW,,1\n";
+
+        let by_name = test_fetcher()
+            .with_nickname_column(Some(ColumnRef::Name("name".to_string())))
+            .parse_csv(csv)
+            .unwrap();
+        assert_eq!(by_name[1].callsign, "K4MW");
+        assert_eq!(by_name[1].nickname, None);
+        assert_eq!(by_name[0].nickname.as_deref(), Some("James"));
+
+        let by_index = test_fetcher()
+            .with_nickname_column(Some(ColumnRef::Index(1)))
+            .parse_csv(csv)
+            .unwrap();
+        assert_eq!(by_index[0].nickname.as_deref(), Some("James"));
+
+        assert!(
+            test_fetcher()
+                .with_nickname_column(Some(ColumnRef::Name("First".to_string())))
+                .parse_csv(csv)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_with_extra_columns() {
+        let csv = "Call,State,Number\nW6JSV,CA,10\nK4MW,,1\n";
+        let members = test_fetcher()
+            .with_extra_columns(vec!["state".to_string()])
+            .parse_csv(csv)
+            .unwrap();
+        assert_eq!(members[0].fields["state"], "CA");
+        assert_eq!(members[1].fields.get("state"), None);
+
+        assert!(
+            test_fetcher()
+                .with_extra_columns(vec!["Grid".to_string()])
+                .parse_csv(csv)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_keeps_roster_order() {
+        let csv = "Call,Number\nW6JSV,10\nK4MW,1\nWN7JT,2\n";
+        let members = test_fetcher().parse_csv(csv).unwrap();
+        let calls = |order: &SortOrder| -> Vec<&str> {
+            order
+                .sorted(&members)
+                .iter()
+                .map(|m| m.callsign.as_str())
+                .collect()
+        };
+        assert_eq!(calls(&SortOrder::Roster), ["W6JSV", "K4MW", "WN7JT"]);
+        assert_eq!(calls(&SortOrder::Callsign), ["K4MW", "W6JSV", "WN7JT"]);
+    }
+
     #[test]
     fn test_find_column_with_whitespace() {
         let fetcher = CsvFetcher::new(
@@ -0,0 +1,648 @@
+//! DXCC entity resolution from a local country file.
+//!
+//! Reads the AD1C country files (`cty.dat`, or the `cty.csv` variant) and
+//! resolves callsigns to their DXCC entity by longest prefix match, with
+//! exact-call entries (`=CALL`) taking precedence. Per-prefix CQ zone,
+//! ITU zone and continent overrides (`(n)`, `[n]`, `{XX}`) are honored;
+//! latitude/longitude and UTC offset overrides are ignored.
+//!
+//! `cty.dat` records look like:
+//!
+//! ```text
+//! Fed. Rep. of Germany:     14:  28:  EU:   51.00:   -10.00:    -1.0:  DL:
+//!     DA,DB,DC,DD,DE,DF,DG,DH,DI,DJ,DK,DL,DM,DN,DO,DP,DQ,DR,=DL0XYZ(15);
+//! ```
+//!
+//! The country files don't carry ISO codes, so the ISO 3166 country is taken
+//! from a built-in table keyed on the entity's primary prefix. Entities that
+//! aren't part of any country (e.g. the Spratly Islands) have none.
+
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::info;
+
+/// A resolved DXCC entity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DxccEntity {
+    pub name: String,
+    /// Primary prefix from the country file, e.g. `DL` or `VP8/h`.
+    pub primary_prefix: String,
+    pub continent: String,
+    pub cq_zone: u8,
+    pub itu_zone: u8,
+    /// ISO 3166-1 alpha-2 code, if the entity belongs to a country.
+    pub iso_country: Option<String>,
+}
+
+impl DxccEntity {
+    /// The country's flag as a pair of regional indicator symbols.
+    pub fn flag(&self) -> Option<String> {
+        let iso = self.iso_country.as_deref()?;
+        iso.chars()
+            .map(|c| char::from_u32(0x1F1E6 + (c.to_ascii_uppercase() as u32 - 'A' as u32)))
+            .collect()
+    }
+}
+
+/// Prefix and exact-call tables loaded from a country file.
+#[derive(Debug, Default)]
+pub struct DxccResolver {
+    prefixes: HashMap<String, DxccEntity>,
+    exact: HashMap<String, DxccEntity>,
+    max_prefix_len: usize,
+}
+
+/// Suffixes that say how an operator is operating rather than where.
+const IGNORED_SUFFIXES: &[&str] = &["P", "M", "MM", "AM", "QRP", "A", "R"];
+
+impl DxccResolver {
+    /// Load a country file. The format is picked by extension: `.csv` for
+    /// `cty.csv`, anything else is parsed as `cty.dat`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read country file: {}", path.display()))?;
+
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let resolver = if is_csv {
+            Self::parse_csv(&content)
+        } else {
+            Self::parse_dat(&content)
+        }
+        .with_context(|| format!("Failed to parse country file: {}", path.display()))?;
+
+        info!(
+            "Loaded {} DXCC prefixes and {} exact calls from {}",
+            resolver.prefixes.len(),
+            resolver.exact.len(),
+            path.display()
+        );
+        Ok(resolver)
+    }
+
+    fn parse_dat(content: &str) -> Result<Self> {
+        let mut resolver = Self::default();
+        for record in content.split(';') {
+            let record = record.trim();
+            if record.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = record.splitn(9, ':').collect();
+            if fields.len() != 9 {
+                bail!("Malformed record: {}", record.lines().next().unwrap_or(""));
+            }
+            let entity = entity_from_fields(fields[0], fields[7], fields[3], fields[1], fields[2])?;
+            for token in fields[8].split([',', '\n', '\r', ' ']) {
+                resolver.add_token(token, &entity);
+            }
+        }
+        Ok(resolver)
+    }
+
+    fn parse_csv(content: &str) -> Result<Self> {
+        let mut resolver = Self::default();
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(content.as_bytes());
+
+        for record in reader.records() {
+            let record = record?;
+            if record.len() < 10 {
+                bail!("Expected 10 columns, found {}", record.len());
+            }
+            let entity =
+                entity_from_fields(&record[1], &record[0], &record[3], &record[4], &record[5])?;
+            for token in record[9].trim_end_matches(';').split_whitespace() {
+                resolver.add_token(token, &entity);
+            }
+        }
+        Ok(resolver)
+    }
+
+    /// Add one prefix-list token such as `DA`, `=DL0XYZ(15)` or `UA9[31]`.
+    fn add_token(&mut self, token: &str, entity: &DxccEntity) {
+        let token = token.trim();
+        if token.is_empty() {
+            return;
+        }
+        let (exact, token) = match token.strip_prefix('=') {
+            Some(rest) => (true, rest),
+            None => (false, token),
+        };
+        let end = token.find(['(', '[', '<', '{', '~']).unwrap_or(token.len());
+        let key = token[..end].to_uppercase();
+        if key.is_empty() {
+            return;
+        }
+
+        let mut entity = entity.clone();
+        if let Some(cq) = between(token, '(', ')').and_then(|v| v.parse().ok()) {
+            entity.cq_zone = cq;
+        }
+        if let Some(itu) = between(token, '[', ']').and_then(|v| v.parse().ok()) {
+            entity.itu_zone = itu;
+        }
+        if let Some(continent) = between(token, '{', '}') {
+            entity.continent = continent.to_string();
+        }
+
+        if exact {
+            self.exact.insert(key, entity);
+        } else {
+            self.max_prefix_len = self.max_prefix_len.max(key.len());
+            self.prefixes.insert(key, entity);
+        }
+    }
+
+    /// Resolve a callsign to its DXCC entity.
+    ///
+    /// Portable calls use the prefix part (`DL/W6JSV` and `W6JSV/DL` both
+    /// resolve as `DL`), and suffixes like `/P` or `/QRP` are ignored.
+    pub fn resolve(&self, callsign: &str) -> Option<&DxccEntity> {
+        let callsign = callsign.trim().to_uppercase();
+        if let Some(entity) = self.exact.get(&callsign) {
+            return Some(entity);
+        }
+
+        let parts: Vec<&str> = callsign
+            .split('/')
+            .filter(|p| !p.is_empty() && !IGNORED_SUFFIXES.contains(p))
+            .collect();
+        let base = match parts.as_slice() {
+            [] => return None,
+            [call] => *call,
+            // Call area suffix (W6JSV/7) doesn't change the entity.
+            [a, b, ..] if b.len() == 1 && b.chars().all(|c| c.is_ascii_digit()) => *a,
+            // A portable prefix is the shorter part.
+            [a, b, ..] if b.len() < a.len() => *b,
+            [a, ..] => *a,
+        };
+        if let Some(entity) = self.exact.get(base) {
+            return Some(entity);
+        }
+
+        (1..=base.len().min(self.max_prefix_len))
+            .rev()
+            .find_map(|len| base.get(..len).and_then(|p| self.prefixes.get(p)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty() && self.exact.is_empty()
+    }
+}
+
+fn entity_from_fields(
+    name: &str,
+    primary_prefix: &str,
+    continent: &str,
+    cq_zone: &str,
+    itu_zone: &str,
+) -> Result<DxccEntity> {
+    // WAE-only entities are marked with a leading '*'.
+    let primary_prefix = primary_prefix.trim().trim_start_matches('*').to_string();
+    Ok(DxccEntity {
+        name: name.trim().to_string(),
+        iso_country: iso_country(&primary_prefix).map(str::to_string),
+        continent: continent.trim().to_string(),
+        cq_zone: cq_zone
+            .trim()
+            .parse()
+            .with_context(|| format!("Bad CQ zone for {}", primary_prefix))?,
+        itu_zone: itu_zone
+            .trim()
+            .parse()
+            .with_context(|| format!("Bad ITU zone for {}", primary_prefix))?,
+        primary_prefix,
+    })
+}
+
+fn between(s: &str, open: char, close: char) -> Option<&str> {
+    let start = s.find(open)? + open.len_utf8();
+    let len = s[start..].find(close)?;
+    Some(&s[start..start + len])
+}
+
+/// ISO 3166-1 alpha-2 code for an entity, keyed on its primary prefix.
+fn iso_country(primary_prefix: &str) -> Option<&'static str> {
+    ISO_BY_PRIMARY_PREFIX
+        .iter()
+        .find(|(prefix, _)| *prefix == primary_prefix)
+        .map(|(_, iso)| *iso)
+}
+
+/// Primary prefix -> ISO country for entities that belong to one.
+/// Islands and other separate DXCC entities map to their parent country
+/// (or their own ISO code where one exists, e.g. Åland or Guam).
+const ISO_BY_PRIMARY_PREFIX: &[(&str, &str)] = &[
+    ("3A", "MC"),
+    ("3B6", "MU"),
+    ("3B8", "MU"),
+    ("3B9", "MU"),
+    ("3C", "GQ"),
+    ("3C0", "GQ"),
+    ("3D2", "FJ"),
+    ("3D2/c", "FJ"),
+    ("3D2/r", "FJ"),
+    ("3DA", "SZ"),
+    ("3V", "TN"),
+    ("3W", "VN"),
+    ("3X", "GN"),
+    ("3Y/b", "BV"),
+    ("4J", "AZ"),
+    ("4L", "GE"),
+    ("4O", "ME"),
+    ("4S", "LK"),
+    ("4W", "TL"),
+    ("4X", "IL"),
+    ("5A", "LY"),
+    ("5B", "CY"),
+    ("5H", "TZ"),
+    ("5N", "NG"),
+    ("5R", "MG"),
+    ("5T", "MR"),
+    ("5U", "NE"),
+    ("5V", "TG"),
+    ("5W", "WS"),
+    ("5X", "UG"),
+    ("5Z", "KE"),
+    ("6O", "SO"),
+    ("6W", "SN"),
+    ("6Y", "JM"),
+    ("7O", "YE"),
+    ("7P", "LS"),
+    ("7Q", "MW"),
+    ("7X", "DZ"),
+    ("8P", "BB"),
+    ("8Q", "MV"),
+    ("8R", "GY"),
+    ("9A", "HR"),
+    ("9G", "GH"),
+    ("9H", "MT"),
+    ("9J", "ZM"),
+    ("9K", "KW"),
+    ("9L", "SL"),
+    ("9M2", "MY"),
+    ("9M6", "MY"),
+    ("9N", "NP"),
+    ("9Q", "CD"),
+    ("9U", "BI"),
+    ("9V", "SG"),
+    ("9X", "RW"),
+    ("9Y", "TT"),
+    ("A2", "BW"),
+    ("A3", "TO"),
+    ("A4", "OM"),
+    ("A5", "BT"),
+    ("A6", "AE"),
+    ("A7", "QA"),
+    ("A9", "BH"),
+    ("AP", "PK"),
+    ("BV", "TW"),
+    ("BV9P", "TW"),
+    ("BY", "CN"),
+    ("C2", "NR"),
+    ("C3", "AD"),
+    ("C5", "GM"),
+    ("C6", "BS"),
+    ("C9", "MZ"),
+    ("CE", "CL"),
+    ("CE0X", "CL"),
+    ("CE0Y", "CL"),
+    ("CE0Z", "CL"),
+    ("CE9", "AQ"),
+    ("CM", "CU"),
+    ("CN", "MA"),
+    ("CP", "BO"),
+    ("CT", "PT"),
+    ("CT3", "PT"),
+    ("CU", "PT"),
+    ("CX", "UY"),
+    ("CY0", "CA"),
+    ("CY9", "CA"),
+    ("D2", "AO"),
+    ("D4", "CV"),
+    ("D6", "KM"),
+    ("DL", "DE"),
+    ("DU", "PH"),
+    ("E3", "ER"),
+    ("E4", "PS"),
+    ("E5/n", "CK"),
+    ("E5/s", "CK"),
+    ("E6", "NU"),
+    ("E7", "BA"),
+    ("EA", "ES"),
+    ("EA6", "ES"),
+    ("EA8", "ES"),
+    ("EA9", "ES"),
+    ("EI", "IE"),
+    ("EK", "AM"),
+    ("EL", "LR"),
+    ("EP", "IR"),
+    ("ER", "MD"),
+    ("ES", "EE"),
+    ("ET", "ET"),
+    ("EU", "BY"),
+    ("EX", "KG"),
+    ("EY", "TJ"),
+    ("EZ", "TM"),
+    ("F", "FR"),
+    ("FG", "GP"),
+    ("FH", "YT"),
+    ("FJ", "BL"),
+    ("FK", "NC"),
+    ("FK/c", "NC"),
+    ("FM", "MQ"),
+    ("FO", "PF"),
+    ("FO/a", "PF"),
+    ("FO/c", "FR"),
+    ("FO/m", "PF"),
+    ("FP", "PM"),
+    ("FR", "RE"),
+    ("FR/g", "TF"),
+    ("FR/j", "TF"),
+    ("FR/t", "TF"),
+    ("FS", "MF"),
+    ("FT5W", "TF"),
+    ("FT5X", "TF"),
+    ("FT5Z", "TF"),
+    ("FW", "WF"),
+    ("FY", "GF"),
+    ("G", "GB"),
+    ("GD", "IM"),
+    ("GI", "GB"),
+    ("GJ", "JE"),
+    ("GM", "GB"),
+    ("GM/s", "GB"),
+    ("GU", "GG"),
+    ("GW", "GB"),
+    ("H4", "SB"),
+    ("H40", "SB"),
+    ("HA", "HU"),
+    ("HB", "CH"),
+    ("HB0", "LI"),
+    ("HC", "EC"),
+    ("HC8", "EC"),
+    ("HH", "HT"),
+    ("HI", "DO"),
+    ("HK", "CO"),
+    ("HK0/a", "CO"),
+    ("HK0/m", "CO"),
+    ("HL", "KR"),
+    ("HP", "PA"),
+    ("HR", "HN"),
+    ("HS", "TH"),
+    ("HV", "VA"),
+    ("HZ", "SA"),
+    ("I", "IT"),
+    ("IG9", "IT"),
+    ("IS", "IT"),
+    ("IT9", "IT"),
+    ("J2", "DJ"),
+    ("J3", "GD"),
+    ("J5", "GW"),
+    ("J6", "LC"),
+    ("J7", "DM"),
+    ("J8", "VC"),
+    ("JA", "JP"),
+    ("JD/m", "JP"),
+    ("JD/o", "JP"),
+    ("JT", "MN"),
+    ("JW", "SJ"),
+    ("JW/b", "SJ"),
+    ("JX", "SJ"),
+    ("K", "US"),
+    ("KG4", "US"),
+    ("KH0", "MP"),
+    ("KH1", "UM"),
+    ("KH2", "GU"),
+    ("KH3", "UM"),
+    ("KH4", "UM"),
+    ("KH5", "UM"),
+    ("KH6", "US"),
+    ("KH7K", "US"),
+    ("KH8", "AS"),
+    ("KH8/s", "AS"),
+    ("KH9", "UM"),
+    ("KL", "US"),
+    ("KP1", "UM"),
+    ("KP2", "VI"),
+    ("KP4", "PR"),
+    ("KP5", "PR"),
+    ("LA", "NO"),
+    ("LU", "AR"),
+    ("LX", "LU"),
+    ("LY", "LT"),
+    ("LZ", "BG"),
+    ("OA", "PE"),
+    ("OD", "LB"),
+    ("OE", "AT"),
+    ("OH", "FI"),
+    ("OH0", "AX"),
+    ("OJ0", "AX"),
+    ("OK", "CZ"),
+    ("OM", "SK"),
+    ("ON", "BE"),
+    ("OX", "GL"),
+    ("OY", "FO"),
+    ("OZ", "DK"),
+    ("P2", "PG"),
+    ("P4", "AW"),
+    ("P5", "KP"),
+    ("PA", "NL"),
+    ("PJ2", "CW"),
+    ("PJ4", "BQ"),
+    ("PJ5", "BQ"),
+    ("PJ7", "SX"),
+    ("PY", "BR"),
+    ("PY0F", "BR"),
+    ("PY0S", "BR"),
+    ("PY0T", "BR"),
+    ("PZ", "SR"),
+    ("R1FJ", "RU"),
+    ("S0", "EH"),
+    ("S2", "BD"),
+    ("S5", "SI"),
+    ("S7", "SC"),
+    ("S9", "ST"),
+    ("SM", "SE"),
+    ("SP", "PL"),
+    ("ST", "SD"),
+    ("SU", "EG"),
+    ("SV", "GR"),
+    ("SV/a", "GR"),
+    ("SV5", "GR"),
+    ("SV9", "GR"),
+    ("T2", "TV"),
+    ("T30", "KI"),
+    ("T31", "KI"),
+    ("T32", "KI"),
+    ("T33", "KI"),
+    ("T5", "SO"),
+    ("T7", "SM"),
+    ("T8", "PW"),
+    ("TA", "TR"),
+    ("TA1", "TR"),
+    ("TF", "IS"),
+    ("TG", "GT"),
+    ("TI", "CR"),
+    ("TI9", "CR"),
+    ("TJ", "CM"),
+    ("TK", "FR"),
+    ("TL", "CF"),
+    ("TN", "CG"),
+    ("TR", "GA"),
+    ("TT", "TD"),
+    ("TU", "CI"),
+    ("TY", "BJ"),
+    ("TZ", "ML"),
+    ("UA", "RU"),
+    ("UA2", "RU"),
+    ("UA9", "RU"),
+    ("UK", "UZ"),
+    ("UN", "KZ"),
+    ("UR", "UA"),
+    ("V2", "AG"),
+    ("V3", "BZ"),
+    ("V4", "KN"),
+    ("V5", "NA"),
+    ("V6", "FM"),
+    ("V7", "MH"),
+    ("V8", "BN"),
+    ("VE", "CA"),
+    ("VK", "AU"),
+    ("VK0H", "HM"),
+    ("VK0M", "AU"),
+    ("VK9C", "CC"),
+    ("VK9L", "AU"),
+    ("VK9M", "AU"),
+    ("VK9N", "NF"),
+    ("VK9W", "AU"),
+    ("VK9X", "CX"),
+    ("VP2E", "AI"),
+    ("VP2M", "MS"),
+    ("VP2V", "VG"),
+    ("VP5", "TC"),
+    ("VP6", "PN"),
+    ("VP6/d", "PN"),
+    ("VP8", "FK"),
+    ("VP8/g", "GS"),
+    ("VP8/s", "GS"),
+    ("VP9", "BM"),
+    ("VQ9", "IO"),
+    ("VR", "HK"),
+    ("VU", "IN"),
+    ("VU4", "IN"),
+    ("VU7", "IN"),
+    ("XE", "MX"),
+    ("XF4", "MX"),
+    ("XT", "BF"),
+    ("XU", "KH"),
+    ("XW", "LA"),
+    ("XX9", "MO"),
+    ("XZ", "MM"),
+    ("YA", "AF"),
+    ("YB", "ID"),
+    ("YI", "IQ"),
+    ("YJ", "VU"),
+    ("YK", "SY"),
+    ("YL", "LV"),
+    ("YN", "NI"),
+    ("YO", "RO"),
+    ("YS", "SV"),
+    ("YU", "RS"),
+    ("YV", "VE"),
+    ("YV0", "VE"),
+    ("Z2", "ZW"),
+    ("Z3", "MK"),
+    ("Z6", "XK"),
+    ("Z8", "SS"),
+    ("ZA", "AL"),
+    ("ZB", "GI"),
+    ("ZC4", "CY"),
+    ("ZD7", "SH"),
+    ("ZD8", "SH"),
+    ("ZD9", "SH"),
+    ("ZF", "KY"),
+    ("ZK3", "TK"),
+    ("ZL", "NZ"),
+    ("ZL7", "NZ"),
+    ("ZL8", "NZ"),
+    ("ZL9", "NZ"),
+    ("ZP", "PY"),
+    ("ZS", "ZA"),
+    ("ZS8", "ZA"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTY_DAT: &str = "\
+Fed. Rep. of Germany:     14:  28:  EU:   51.00:   -10.00:    -1.0:  DL:
+    DA,DB,DC,DD,DE,DF,DG,DH,DI,DJ,DK,DL,DM,DN,DO,DP,DQ,DR,=DL0XYZ(15);
+United States:            05:  08:  NA:   37.53:    91.67:     5.0:  K:
+    AA,AB,K,N,W,=W6JSV(3)[6],KH6XX{OC};
+Spratly Islands:          26:  50:  AS:    9.88:  -114.23:    -8.0:  1S:
+    1S,9M0;
+";
+
+    #[test]
+    fn test_resolve_longest_prefix_and_exact() {
+        let resolver = DxccResolver::parse_dat(CTY_DAT).unwrap();
+
+        let dl = resolver.resolve("DL1ABC").unwrap();
+        assert_eq!(dl.name, "Fed. Rep. of Germany");
+        assert_eq!(dl.continent, "EU");
+        assert_eq!((dl.cq_zone, dl.itu_zone), (14, 28));
+        assert_eq!(dl.flag().as_deref(), Some("🇩🇪"));
+
+        // Exact call with a zone override.
+        assert_eq!(resolver.resolve("dl0xyz").unwrap().cq_zone, 15);
+        let w6jsv = resolver.resolve("W6JSV").unwrap();
+        assert_eq!((w6jsv.cq_zone, w6jsv.itu_zone), (3, 6));
+        assert_eq!(resolver.resolve("W6ABC").unwrap().cq_zone, 5);
+
+        // Longer prefix wins, with its continent override.
+        assert_eq!(resolver.resolve("KH6XXA").unwrap().continent, "OC");
+
+        let spratly = resolver.resolve("9M0A").unwrap();
+        assert_eq!(spratly.iso_country, None);
+        assert_eq!(spratly.flag(), None);
+
+        assert!(resolver.resolve("ZZ9ZZZ").is_none());
+    }
+
+    #[test]
+    fn test_resolve_portable_calls() {
+        let resolver = DxccResolver::parse_dat(CTY_DAT).unwrap();
+
+        assert_eq!(resolver.resolve("DL/W1AW").unwrap().primary_prefix, "DL");
+        assert_eq!(resolver.resolve("W1AW/DL").unwrap().primary_prefix, "DL");
+        assert_eq!(resolver.resolve("DL1ABC/P").unwrap().primary_prefix, "DL");
+        assert_eq!(resolver.resolve("W1AW/QRP").unwrap().primary_prefix, "K");
+        assert_eq!(resolver.resolve("W6ABC/7").unwrap().primary_prefix, "K");
+    }
+
+    #[test]
+    fn test_parse_cty_csv() {
+        let csv = "\
+DL,Fed. Rep. of Germany,230,EU,14,28,51.00,-10.00,-1.0,DA DB DC DL =DL0XYZ(15);
+*TA1,European Turkey,390,EU,20,39,41.02,-28.97,-2.0,TA1 =TC0A;
+";
+        let resolver = DxccResolver::parse_csv(csv).unwrap();
+
+        assert_eq!(
+            resolver.resolve("DB2XY").unwrap().iso_country.as_deref(),
+            Some("DE")
+        );
+        assert_eq!(resolver.resolve("DL0XYZ").unwrap().cq_zone, 15);
+
+        let turkey = resolver.resolve("TC0A").unwrap();
+        assert_eq!(turkey.primary_prefix, "TA1");
+        assert_eq!(turkey.flag().as_deref(), Some("🇹🇷"));
+    }
+}
@@ -0,0 +1,232 @@
+//! Machine-readable roster exports.
+//!
+//! JSON and CSV versions of an org's final member list, for tools that
+//! would otherwise scrape the notes file. The schema is documented in
+//! `docs/export-schema.md`; bump [`SCHEMA_VERSION`] on any change that
+//! isn't purely additive.
+
+use crate::csv_fetcher::{Member, Source};
+use crate::sort::SortOrder;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Export<'a> {
+    schema_version: u32,
+    org: &'a str,
+    label: &'a str,
+    member_count: usize,
+    members: Vec<ExportMember<'a>>,
+}
+
+#[derive(Serialize)]
+struct ExportMember<'a> {
+    callsign: &'a str,
+    roster_callsign: &'a str,
+    member_id: &'a str,
+    nickname: Option<&'a str>,
+    nickname_source: Option<Source>,
+    remapped: bool,
+    remap_source: Option<Source>,
+    remapped_since: Option<DateTime<Utc>>,
+    dxcc: Option<ExportDxcc<'a>>,
+    license_expires: Option<NaiveDate>,
+    license_source: Option<&'a str>,
+    license_problem: Option<String>,
+    fields: BTreeMap<&'a str, &'a str>,
+}
+
+#[derive(Serialize)]
+struct ExportDxcc<'a> {
+    name: &'a str,
+    continent: &'a str,
+    cq_zone: u8,
+    itu_zone: u8,
+    country: Option<&'a str>,
+}
+
+impl<'a> ExportMember<'a> {
+    fn new(member: &'a Member, fields: &'a [String]) -> Self {
+        Self {
+            callsign: &member.callsign,
+            roster_callsign: &member.roster_callsign,
+            member_id: &member.member_id,
+            nickname: member.nickname.as_deref(),
+            nickname_source: member.nickname_source,
+            remapped: member.is_remapped(),
+            remap_source: member.remap_source,
+            remapped_since: member.remapped_since,
+            dxcc: member.dxcc.as_ref().map(|d| ExportDxcc {
+                name: &d.name,
+                continent: &d.continent,
+                cq_zone: d.cq_zone,
+                itu_zone: d.itu_zone,
+                country: d.iso_country.as_deref(),
+            }),
+            license_expires: member.license.as_ref().and_then(|l| l.expires),
+            license_source: member.license.as_ref().map(|l| l.source),
+            license_problem: member.license_problem.map(|p| p.to_string()),
+            // Only the listed columns, not everything fetched for templates
+            // or other outputs
+            fields: fields
+                .iter()
+                .filter_map(|f| Some((f.as_str(), member.fields.get(f)?.as_str())))
+                .collect(),
+        }
+    }
+}
+
+/// Render members as a JSON document, in `sort` order. `fields` are the
+/// roster columns to include under each member's `fields`.
+pub fn json(
+    org: &str,
+    label: &str,
+    members: &[Member],
+    fields: &[String],
+    sort: &SortOrder,
+) -> Result<String> {
+    let export = Export {
+        schema_version: SCHEMA_VERSION,
+        org,
+        label,
+        member_count: members.len(),
+        members: sort
+            .sorted(members)
+            .into_iter()
+            .map(|member| ExportMember::new(member, fields))
+            .collect(),
+    };
+    let mut output = serde_json::to_string_pretty(&export)?;
+    output.push('\n');
+    Ok(output)
+}
+
+/// Render members as CSV, in `sort` order. `fields` are the roster columns
+/// to include, each as a `field:<name>` column.
+pub fn csv(members: &[Member], fields: &[String], sort: &SortOrder) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut header: Vec<String> = [
+        "schema_version",
+        "callsign",
+        "roster_callsign",
+        "member_id",
+        "nickname",
+        "nickname_source",
+        "remapped",
+        "remap_source",
+        "remapped_since",
+        "dxcc",
+        "continent",
+        "cq_zone",
+        "itu_zone",
+        "country",
+        "license_expires",
+        "license_source",
+        "license_problem",
+    ]
+    .iter()
+    .map(|h| h.to_string())
+    .collect();
+    header.extend(fields.iter().map(|f| format!("field:{}", f)));
+    writer.write_record(&header)?;
+
+    let source = |s: Option<Source>| s.map(|s| s.as_str().to_string()).unwrap_or_default();
+    for member in sort.sorted(members) {
+        let dxcc = member.dxcc.as_ref();
+        let license = member.license.as_ref();
+        let mut row = vec![
+            SCHEMA_VERSION.to_string(),
+            member.callsign.clone(),
+            member.roster_callsign.clone(),
+            member.member_id.clone(),
+            member.nickname.clone().unwrap_or_default(),
+            source(member.nickname_source),
+            member.is_remapped().to_string(),
+            source(member.remap_source),
+            member
+                .remapped_since
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+            dxcc.map(|d| d.name.clone()).unwrap_or_default(),
+            dxcc.map(|d| d.continent.clone()).unwrap_or_default(),
+            dxcc.map(|d| d.cq_zone.to_string()).unwrap_or_default(),
+            dxcc.map(|d| d.itu_zone.to_string()).unwrap_or_default(),
+            dxcc.and_then(|d| d.iso_country.clone()).unwrap_or_default(),
+            license
+                .and_then(|l| l.expires)
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+            license.map(|l| l.source.to_string()).unwrap_or_default(),
+            member
+                .license_problem
+                .map(|p| p.to_string())
+                .unwrap_or_default(),
+        ];
+        row.extend(
+            fields
+                .iter()
+                .map(|f| member.fields.get(f).cloned().unwrap_or_default()),
+        );
+        writer.write_record(&row)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members() -> Vec<Member> {
+        let mut remapped = Member::new("W1NEW".to_string(), "12".to_string());
+        remapped.roster_callsign = "K1OLD".to_string();
+        remapped.nickname = Some("Bob".to_string());
+        remapped.nickname_source = Some(Source::Qrz);
+        remapped.remap_source = Some(Source::Qrz);
+        remapped
+            .fields
+            .insert("State".to_string(), "CT".to_string());
+        remapped
+            .fields
+            .insert("Joined".to_string(), "2020-01-01".to_string());
+        vec![remapped, Member::new("K4MW".to_string(), "1".to_string())]
+    }
+
+    #[test]
+    fn test_json_export() {
+        let output = json(
+            "qrqcrew",
+            "QRQ Crew",
+            &members(),
+            &["State".to_string()],
+            &SortOrder::Callsign,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["member_count"], 2);
+        let w1new = &value["members"][1];
+        assert_eq!(w1new["callsign"], "W1NEW");
+        assert_eq!(w1new["roster_callsign"], "K1OLD");
+        assert_eq!(w1new["remapped"], true);
+        assert_eq!(w1new["remap_source"], "qrz");
+        assert_eq!(w1new["fields"]["State"], "CT");
+        // Fetched for something else, so not exported
+        assert!(w1new["fields"]["Joined"].is_null());
+        assert!(value["members"][0]["nickname"].is_null());
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let output = csv(&members(), &["State".to_string()], &SortOrder::Callsign).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("schema_version,callsign,roster_callsign,member_id,"));
+        assert!(lines[0].ends_with(",license_problem,field:State"));
+        assert!(lines[1].starts_with("1,K4MW,K4MW,1,,,false,"));
+        assert!(lines[2].starts_with("1,W1NEW,K1OLD,12,Bob,qrz,true,qrz,"));
+        assert!(lines[2].ends_with(",CT"));
+    }
+}
@@ -0,0 +1,370 @@
+//! Files published from an org's final member list.
+//!
+//! Each output in [`Organization::outputs`] pairs a path with a
+//! [`NotesFormatter`]. Adding a format means adding an implementation and
+//! an arm in [`formatter`]; the sync loop only sees the trait. Any output
+//! can be split into shards (see the `shard` module).
+
+use crate::call_history::{self, CallHistory};
+use crate::config::{LicensePolicy, Organization, OutputConfig};
+use crate::csv_fetcher::Member;
+use crate::export;
+use crate::notes_generator::{Header, NotesGenerator};
+use crate::scp;
+use crate::shard::Sharding;
+use crate::sort::SortOrder;
+use crate::template::LineTemplate;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+
+pub trait NotesFormatter: Send + Sync {
+    /// What the file is, for commit messages (e.g. "call history"). `None`
+    /// for the notes file itself.
+    fn kind(&self) -> Option<&str>;
+
+    /// Roster columns the format reads, fetched along with the roster.
+    fn roster_fields(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Render `members`. `generated` is the header timestamp, for formats
+    /// that have one.
+    fn format(&self, members: &[Member], generated: DateTime<Utc>) -> Result<String>;
+
+    /// Render one shard: the lines for callsigns `in_shard` accepts. The
+    /// default renders the members whose current callsign is in the shard;
+    /// formats with lines for other callsigns render from all of `members`.
+    fn format_shard(
+        &self,
+        members: &[Member],
+        in_shard: &dyn Fn(&str, &Member) -> bool,
+        generated: DateTime<Utc>,
+    ) -> Result<String> {
+        let members: Vec<Member> = members
+            .iter()
+            .filter(|m| in_shard(&m.callsign, m))
+            .cloned()
+            .collect();
+        self.format(&members, generated)
+    }
+}
+
+/// A file to publish and how to render it.
+pub struct Output {
+    pub path: String,
+    pub formatter: Box<dyn NotesFormatter>,
+    /// Publish one file per shard (plus an index) instead of `path`
+    pub shards: Option<Sharding>,
+}
+
+impl Output {
+    /// Every path the output writes: `path`, or each shard and the index.
+    pub fn published_paths(&self) -> Vec<String> {
+        match &self.shards {
+            Some(shards) => {
+                let mut published = shards.paths();
+                published.push(shards.index_path().to_string());
+                published
+            }
+            None => vec![self.path.clone()],
+        }
+    }
+}
+
+/// Every output the org publishes, in config order. Paths shared with
+/// another output are caught at config load, across every org.
+pub fn for_org(org: &Organization) -> Result<Vec<Output>> {
+    let sort = org.sort_order()?;
+    let mut outputs = Vec::new();
+    for config in org.outputs() {
+        let context = || format!("Invalid output {} for org {}", config.path, org.name);
+        let formatter = formatter(org, &config, &sort).with_context(context)?;
+        let shards = config
+            .shard_by
+            .as_deref()
+            .map(|by| Sharding::new(by, &config.path, config.index.as_deref()))
+            .transpose()
+            .with_context(context)?;
+        outputs.push(Output {
+            path: config.path,
+            formatter,
+            shards,
+        });
+    }
+    if outputs.is_empty() {
+        bail!("Org {} has no output_file or outputs", org.name);
+    }
+    Ok(outputs)
+}
+
+/// The formatter for one output of `org`.
+pub fn formatter(
+    org: &Organization,
+    config: &OutputConfig,
+    sort: &SortOrder,
+) -> Result<Box<dyn NotesFormatter>> {
+    Ok(match config.format.as_str() {
+        "notes" => {
+            let template = match &config.line_template {
+                Some(template) => LineTemplate::parse(template)?,
+                None => org.line_template()?,
+            };
+            Box::new(Notes(notes_generator(org, template, sort)))
+        }
+        "call_history" => Box::new(CallHistoryFile {
+            history: match &config.columns {
+                Some(columns) => CallHistory::parse(columns)?,
+                None => org.call_history()?,
+            },
+            emoji: org.emoji.clone(),
+            label: org.label.clone(),
+            sort: sort.clone(),
+        }),
+        "json" => Box::new(JsonExport {
+            name: org.name.clone(),
+            label: org.label.clone(),
+            fields: config
+                .fields
+                .clone()
+                .unwrap_or_else(|| org.export_fields.clone()),
+            sort: sort.clone(),
+        }),
+        "csv" => Box::new(CsvExport {
+            fields: config
+                .fields
+                .clone()
+                .unwrap_or_else(|| org.export_fields.clone()),
+            sort: sort.clone(),
+        }),
+        "scp" => Box::new(Scp),
+        other => bail!(
+            "unknown format {:?} (expected notes, call_history, json, csv or scp)",
+            other
+        ),
+    })
+}
+
+fn notes_generator(org: &Organization, template: LineTemplate, sort: &SortOrder) -> NotesGenerator {
+    let header = Header::from_config(
+        &org.header,
+        vec![
+            ("org".to_string(), org.name.clone()),
+            ("source".to_string(), org.roster_url.clone()),
+            ("source_type".to_string(), org.source_type.clone()),
+        ],
+    );
+    let mut generator =
+        NotesGenerator::new(org.emoji.clone(), org.label.clone(), org.header.url.clone())
+            .with_flags(org.flag_emoji)
+            .with_template(template)
+            .with_header(header)
+            .with_sort(sort.clone());
+    if org.former_calls {
+        generator = generator.with_former_calls(
+            org.former_call_marker
+                .clone()
+                .unwrap_or_else(|| "→".to_string()),
+            org.former_call_max_age_days.map(chrono::Duration::days),
+        );
+    }
+    if org.license_policy == LicensePolicy::Flag {
+        generator = generator
+            .with_license_flag(org.license_flag.clone().unwrap_or_else(|| "⚠️".to_string()));
+    }
+    generator
+}
+
+/// The Ham2K PoLo notes file.
+struct Notes(NotesGenerator);
+
+impl NotesFormatter for Notes {
+    fn kind(&self) -> Option<&str> {
+        None
+    }
+
+    fn roster_fields(&self) -> Vec<String> {
+        self.0.roster_fields()
+    }
+
+    fn format(&self, members: &[Member], generated: DateTime<Utc>) -> Result<String> {
+        Ok(self.0.generate_at(members, generated))
+    }
+
+    fn format_shard(
+        &self,
+        members: &[Member],
+        in_shard: &dyn Fn(&str, &Member) -> bool,
+        generated: DateTime<Utc>,
+    ) -> Result<String> {
+        Ok(self.0.generate_shard_at(members, in_shard, generated))
+    }
+}
+
+struct CallHistoryFile {
+    history: CallHistory,
+    emoji: String,
+    label: String,
+    sort: SortOrder,
+}
+
+impl NotesFormatter for CallHistoryFile {
+    fn kind(&self) -> Option<&str> {
+        Some("call history")
+    }
+
+    fn roster_fields(&self) -> Vec<String> {
+        self.history.roster_fields()
+    }
+
+    fn format(&self, members: &[Member], _generated: DateTime<Utc>) -> Result<String> {
+        let entries: Vec<call_history::Entry> = self
+            .sort
+            .sorted(members)
+            .into_iter()
+            .map(|member| call_history::Entry {
+                member,
+                emoji: &self.emoji,
+                label: &self.label,
+            })
+            .collect();
+        let title = format!("{} call history", self.label);
+        Ok(self.history.generate(&title, &entries))
+    }
+}
+
+struct JsonExport {
+    name: String,
+    label: String,
+    fields: Vec<String>,
+    sort: SortOrder,
+}
+
+impl NotesFormatter for JsonExport {
+    fn kind(&self) -> Option<&str> {
+        Some("JSON export")
+    }
+
+    fn roster_fields(&self) -> Vec<String> {
+        self.fields.clone()
+    }
+
+    fn format(&self, members: &[Member], _generated: DateTime<Utc>) -> Result<String> {
+        export::json(&self.name, &self.label, members, &self.fields, &self.sort)
+    }
+}
+
+struct CsvExport {
+    fields: Vec<String>,
+    sort: SortOrder,
+}
+
+impl NotesFormatter for CsvExport {
+    fn kind(&self) -> Option<&str> {
+        Some("CSV export")
+    }
+
+    fn roster_fields(&self) -> Vec<String> {
+        self.fields.clone()
+    }
+
+    fn format(&self, members: &[Member], _generated: DateTime<Utc>) -> Result<String> {
+        export::csv(members, &self.fields, &self.sort)
+    }
+}
+
+/// Super Check Partial file with just this org's callsigns.
+struct Scp;
+
+impl NotesFormatter for Scp {
+    fn kind(&self) -> Option<&str> {
+        Some("Super Check Partial")
+    }
+
+    fn format(&self, members: &[Member], _generated: DateTime<Utc>) -> Result<String> {
+        Ok(scp::generate(
+            &[],
+            members.iter().map(|m| m.callsign.as_str()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn org(toml: &str) -> Organization {
+        let base = r#"
+            name = "qrqcrew"
+            roster_url = "https://example.com/roster.csv"
+            emoji = "⚓"
+            label = "QRQ Crew"
+        "#;
+        config::Config::builder()
+            .add_source(config::File::from_str(
+                &format!("{}\n{}", base, toml),
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_outputs_include_legacy_fields() {
+        let org = org(r#"
+            output_file = "qrqcrew-notes.txt"
+            json_export_file = "qrqcrew.json"
+
+            [[outputs]]
+            format = "csv"
+            path = "qrqcrew.csv"
+            fields = ["State"]
+
+            [[outputs]]
+            format = "notes"
+            path = "qrqcrew-short.txt"
+            line_template = "{callsign} {label}"
+        "#);
+        let outputs = for_org(&org).unwrap();
+        let paths: Vec<&str> = outputs.iter().map(|o| o.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "qrqcrew-notes.txt",
+                "qrqcrew.json",
+                "qrqcrew.csv",
+                "qrqcrew-short.txt"
+            ]
+        );
+        assert_eq!(outputs[2].formatter.roster_fields(), ["State"]);
+
+        let members = [Member::new("K4MW".to_string(), "1".to_string())];
+        let short = outputs[3]
+            .formatter
+            .format(&members, DateTime::UNIX_EPOCH)
+            .unwrap();
+        assert!(short.ends_with("\nK4MW QRQ Crew\n"));
+    }
+
+    fn error(toml: &str) -> String {
+        match for_org(&org(toml)) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => format!("{:#}", err),
+        }
+    }
+
+    #[test]
+    fn test_bad_outputs_are_rejected() {
+        let err = error(
+            r#"
+            [[outputs]]
+            format = "pdf"
+            path = "qrqcrew.pdf"
+        "#,
+        );
+        assert!(err.contains("unknown format"));
+
+        assert!(error("").contains("no output_file"));
+    }
+}
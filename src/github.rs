@@ -303,7 +303,7 @@ impl GitHubClient {
 
         // 4. Create new tree with updated files
         let tree_request = CreateTreeRequest {
-            base_tree: base_tree_sha,
+            base_tree: base_tree_sha.clone(),
             tree: tree_entries,
         };
 
@@ -314,6 +314,15 @@ impl GitHubClient {
 
         debug!("Created tree: {}", tree_response.sha);
 
+        // Identical tree: every file already has this content
+        if tree_response.sha == base_tree_sha {
+            info!(
+                "No changes for {}/{}, skipping commit",
+                self.owner, self.repo
+            );
+            return Ok(());
+        }
+
         // 5. Create commit
         let author = CommitAuthor {
             name: self.author_name.clone(),
@@ -6,13 +6,16 @@ use std::error::Error;
 use std::time::Duration;
 use tracing::{debug, warn};
 
-use crate::csv_fetcher::Member;
+use crate::csv_fetcher::{ColumnRef, Member, Source};
+use crate::names::clean_name;
 
 pub struct HtmlFetcher {
     client: reqwest::Client,
     url: String,
     callsign_column_index: usize,
     number_column_index: usize,
+    nickname_column: Option<ColumnRef>,
+    extra_columns: Vec<String>,
     callsign_regex: Regex,
 }
 
@@ -28,10 +31,26 @@ impl HtmlFetcher {
             url,
             callsign_column_index,
             number_column_index,
+            nickname_column: None,
+            extra_columns: Vec::new(),
             callsign_regex: Regex::new(r"^[A-Z]{1,2}\d[A-Z]{1,4}$").unwrap(),
         }
     }
 
+    /// Read member names from this column, cleaned up like QRZ names. A
+    /// name is matched against the table's `<th>` header cells.
+    pub fn with_nickname_column(mut self, column: Option<ColumnRef>) -> Self {
+        self.nickname_column = column;
+        self
+    }
+
+    /// Also read these columns (matched against the `<th>` header cells)
+    /// into `Member::fields`.
+    pub fn with_extra_columns(mut self, columns: Vec<String>) -> Self {
+        self.extra_columns = columns;
+        self
+    }
+
     pub async fn fetch_members(&self) -> Result<Vec<Member>> {
         let html = self.fetch_with_retry(3).await?;
         self.parse_html(&html)
@@ -44,6 +63,14 @@ impl HtmlFetcher {
         let table_selector =
             Selector::parse("table.skcc_table tr").expect("Failed to parse table selector");
         let td_selector = Selector::parse("td").expect("Failed to parse td selector");
+        let th_selector = Selector::parse("th").expect("Failed to parse th selector");
+
+        let mut nickname_col = match &self.nickname_column {
+            Some(ColumnRef::Index(index)) => Some(*index),
+            Some(ColumnRef::Name(_)) | None => None,
+        };
+        let mut extra_cols: Vec<(String, usize)> = Vec::new();
+        let mut headers: Vec<String> = Vec::new();
 
         let mut seen: HashSet<String> = HashSet::new();
         let mut members: Vec<Member> = Vec::new();
@@ -51,8 +78,28 @@ impl HtmlFetcher {
         for (row_num, row) in document.select(&table_selector).enumerate() {
             let cells: Vec<_> = row.select(&td_selector).collect();
 
-            // Skip header rows (they use <th> not <td>)
+            // Skip header rows (they use <th> not <td>), resolving columns
+            // configured by name against the first one.
             if cells.is_empty() {
+                if headers.is_empty() {
+                    headers = row
+                        .select(&th_selector)
+                        .map(|th| th.text().collect::<String>().trim().to_string())
+                        .collect();
+                    let find = |name: &str| {
+                        headers
+                            .iter()
+                            .position(|h| h.eq_ignore_ascii_case(name.trim()))
+                    };
+                    if let Some(ColumnRef::Name(name)) = &self.nickname_column {
+                        nickname_col = find(name);
+                    }
+                    extra_cols = self
+                        .extra_columns
+                        .iter()
+                        .filter_map(|name| find(name).map(|col| (name.clone(), col)))
+                        .collect();
+                }
                 continue;
             }
 
@@ -111,15 +158,37 @@ impl HtmlFetcher {
             }
 
             seen.insert(callsign.clone());
-            members.push(Member {
-                callsign,
-                member_id,
-                nickname: None,
-            });
+            let mut member = Member::new(callsign, member_id);
+            member.roster_nickname = nickname_col
+                .and_then(|col| cells.get(col))
+                .and_then(|cell| clean_name(&cell.text().collect::<String>()));
+            member.nickname = member.roster_nickname.clone();
+            member.nickname_source = member.nickname.as_ref().map(|_| Source::Roster);
+            for (name, col) in &extra_cols {
+                if let Some(cell) = cells.get(*col) {
+                    let value = cell.text().collect::<String>().trim().to_string();
+                    if !value.is_empty() {
+                        member.fields.insert(name.clone(), value);
+                    }
+                }
+            }
+            members.push(member);
+        }
+
+        if let Some(ColumnRef::Name(name)) = &self.nickname_column
+            && nickname_col.is_none()
+        {
+            anyhow::bail!("Could not find nickname column '{}' in HTML table", name);
+        }
+        if let Some(missing) = self
+            .extra_columns
+            .iter()
+            .find(|name| !extra_cols.iter().any(|(found, _)| found == *name))
+        {
+            anyhow::bail!("Could not find column '{}' in HTML table", missing);
         }
 
-        // Sort alphabetically by callsign
-        members.sort_by(|a, b| a.callsign.cmp(&b.callsign));
+        // Roster order, as for CSV rosters
 
         Ok(members)
     }
@@ -216,6 +285,39 @@ mod tests {
         assert_eq!(members[1].member_id, "2C");
     }
 
+    #[test]
+    fn test_parse_skcc_html_with_nickname_column() {
+        let html = r#"
+        <table class="skcc_table">
+            <tr><th>SKCC #</th><th>Call</th><th>Name</th></tr>
+            <tr><td>1</td><td>KC9ECI</td><td>TOM</td></tr>
+            <tr><td>2C</td><td>KI4CIA</td><td></td></tr>
+        </table>
+        "#;
+
+        let fetcher = HtmlFetcher::new("http://example.com".to_string(), 1, 0)
+            .with_nickname_column(Some(ColumnRef::Name("name".to_string())));
+        let members = fetcher.parse_html(html).unwrap();
+        assert_eq!(members[0].nickname.as_deref(), Some("Tom"));
+        assert_eq!(members[1].nickname, None);
+
+        let fetcher = HtmlFetcher::new("http://example.com".to_string(), 1, 0)
+            .with_nickname_column(Some(ColumnRef::Index(2)));
+        let members = fetcher.parse_html(html).unwrap();
+        assert_eq!(members[0].nickname.as_deref(), Some("Tom"));
+
+        let fetcher = HtmlFetcher::new("http://example.com".to_string(), 1, 0)
+            .with_extra_columns(vec!["Name".to_string()]);
+        let members = fetcher.parse_html(html).unwrap();
+        assert_eq!(members[0].fields["Name"], "TOM");
+        assert!(
+            HtmlFetcher::new("http://example.com".to_string(), 1, 0)
+                .with_extra_columns(vec!["State".to_string()])
+                .parse_html(html)
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_callsign_validation() {
         let fetcher = HtmlFetcher::new("http://example.com".to_string(), 1, 0);
@@ -1,15 +1,45 @@
+mod atomic_file;
+pub mod call_history;
+pub mod circuit_breaker;
+pub mod combined_notes;
 pub mod config;
 pub mod csv_fetcher;
+pub mod dxcc;
+pub mod export;
+pub mod formats;
 pub mod github;
+mod hash;
 pub mod html_fetcher;
+pub mod license;
+pub mod lookup_budget;
+pub mod names;
 pub mod nickname_cache;
 pub mod notes_generator;
+pub mod output_state;
+pub mod overrides;
 pub mod qrz;
+pub mod redact;
+pub mod remap_history;
+pub mod report;
+pub mod scp;
+pub mod shard;
+pub mod sort;
+pub mod template;
+pub mod typos;
 
-pub use config::{Config, Organization, QrzConfig};
+pub use call_history::CallHistory;
+pub use circuit_breaker::CircuitBreaker;
+pub use config::{Config, EnrichmentConfig, Organization, QrzConfig};
 pub use csv_fetcher::{CsvFetcher, Member};
+pub use dxcc::DxccResolver;
+pub use formats::NotesFormatter;
 pub use github::{GitHubClient, GitHubTarget, PendingFile};
 pub use html_fetcher::HtmlFetcher;
+pub use license::LicenseChecker;
+pub use lookup_budget::LookupBudget;
 pub use nickname_cache::NicknameCache;
 pub use notes_generator::NotesGenerator;
+pub use overrides::Overrides;
 pub use qrz::QrzClient;
+pub use remap_history::{RejectedRemap, RemapHistory};
+pub use template::LineTemplate;
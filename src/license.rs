@@ -0,0 +1,429 @@
+//! License status checks.
+//!
+//! A member's license data comes from QRZ `<expdate>` (stored with the
+//! cached lookup) or, for US callsigns QRZ has no date for, from
+//! callook.info, which mirrors the FCC ULS. Callook results are kept in
+//! their own small JSON cache so a roster's worth of US calls isn't looked
+//! up every cycle.
+//!
+//! A license is a problem when it's cancelled (callook reports the call as
+//! no longer in the ULS) or past its expiry date plus a grace period. What
+//! happens to those members is up to each org's `license_policy`.
+
+use crate::atomic_file::write_atomic;
+use crate::csv_fetcher::Member;
+use crate::report::render_table;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tracing::{debug, info, warn};
+
+const CALLOOK_URL: &str = "https://callook.info";
+
+/// Callsigns issued by the FCC: K, N, W and AA-AL prefixes.
+static US_CALL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:[KNW][A-Z]?|A[A-L])\d[A-Z]{1,3}$").unwrap());
+
+/// What's known about a member's license.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct License {
+    pub expires: Option<NaiveDate>,
+    /// No longer in the licensing database (cancelled, or expired past the
+    /// renewal grace period).
+    pub cancelled: bool,
+    /// Where the data came from, for the report.
+    pub source: &'static str,
+}
+
+/// Why a member's license needs attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LicenseProblem {
+    Expired,
+    Cancelled,
+}
+
+impl fmt::Display for LicenseProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LicenseProblem::Expired => "expired",
+            LicenseProblem::Cancelled => "cancelled",
+        })
+    }
+}
+
+impl License {
+    /// License data from a QRZ `<expdate>`.
+    pub fn from_qrz(expires: NaiveDate) -> Self {
+        Self {
+            expires: Some(expires),
+            cancelled: false,
+            source: "QRZ",
+        }
+    }
+
+    /// The license's problem as of `today`, if it has one. A license only
+    /// counts as expired `grace_days` after its expiry date.
+    pub fn problem(&self, today: NaiveDate, grace_days: i64) -> Option<LicenseProblem> {
+        if self.cancelled {
+            return Some(LicenseProblem::Cancelled);
+        }
+        match self.expires {
+            Some(expires) if expires + Duration::days(grace_days) < today => {
+                Some(LicenseProblem::Expired)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether a callsign was issued by the FCC (and so can be checked on
+/// callook.info).
+pub fn is_us_call(callsign: &str) -> bool {
+    US_CALL.is_match(callsign)
+}
+
+#[derive(Debug, Deserialize)]
+struct CallookResponse {
+    status: String,
+    #[serde(rename = "otherInfo", default)]
+    other_info: Option<CallookOtherInfo>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CallookOtherInfo {
+    #[serde(rename = "expiryDate", default)]
+    expiry_date: Option<String>,
+}
+
+/// Client for callook.info's JSON API.
+pub struct CallookClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl CallookClient {
+    pub fn new() -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            http,
+            base_url: CALLOOK_URL.to_string(),
+        }
+    }
+
+    /// Look up a US callsign. Returns `None` while callook is refreshing its
+    /// copy of the ULS and can't say.
+    pub async fn lookup(&self, callsign: &str) -> Result<Option<License>> {
+        let url = format!("{}/{}/json", self.base_url, callsign);
+        let text = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .context("callook request failed")?
+            .error_for_status()
+            .context("callook request failed")?
+            .text()
+            .await
+            .context("callook response read failed")?;
+        Self::parse(&text)
+    }
+
+    fn parse(json: &str) -> Result<Option<License>> {
+        let response: CallookResponse =
+            serde_json::from_str(json).context("Failed to parse callook response")?;
+        match response.status.as_str() {
+            "VALID" => {
+                let expires = response
+                    .other_info
+                    .and_then(|o| o.expiry_date)
+                    .and_then(|d| NaiveDate::parse_from_str(&d, "%m/%d/%Y").ok());
+                Ok(Some(License {
+                    expires,
+                    cancelled: false,
+                    source: "callook",
+                }))
+            }
+            "INVALID" => Ok(Some(License {
+                expires: None,
+                cancelled: true,
+                source: "callook",
+            })),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Default for CallookClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CallookEntry {
+    expires: Option<NaiveDate>,
+    cancelled: bool,
+    checked_at: DateTime<Utc>,
+}
+
+/// Fills in license data QRZ didn't provide, from callook.info.
+pub struct LicenseChecker {
+    client: CallookClient,
+    cache_path: PathBuf,
+    cache: BTreeMap<String, CallookEntry>,
+    ttl: Duration,
+    max_lookups: usize,
+    dirty: bool,
+}
+
+impl LicenseChecker {
+    /// Load the callook cache (or start empty if it doesn't exist yet).
+    pub fn load(
+        cache_path: impl AsRef<Path>,
+        ttl: Duration,
+        max_lookups_per_cycle: usize,
+    ) -> Result<Self> {
+        let cache_path = cache_path.as_ref().to_path_buf();
+        let cache = if cache_path.exists() {
+            let content = std::fs::read_to_string(&cache_path).with_context(|| {
+                format!("Failed to read license cache: {}", cache_path.display())
+            })?;
+            if content.trim().is_empty() {
+                BTreeMap::new()
+            } else {
+                serde_json::from_str(&content).with_context(|| {
+                    format!("Failed to parse license cache: {}", cache_path.display())
+                })?
+            }
+        } else {
+            debug!("No license cache file, starting fresh");
+            BTreeMap::new()
+        };
+
+        Ok(Self {
+            client: CallookClient::new(),
+            cache_path,
+            cache,
+            ttl,
+            max_lookups: max_lookups_per_cycle,
+            dirty: false,
+        })
+    }
+
+    /// Set `license` on US members that don't have one yet, from the cache
+    /// or (up to the per-cycle limit) callook.info. Stops looking up for the
+    /// cycle after the first failed request.
+    pub async fn fill(&mut self, org_name: &str, members: &mut [Member]) {
+        let now = Utc::now();
+        let mut lookups = 0;
+        let mut deferred = 0;
+        let mut failed = false;
+
+        for member in members
+            .iter_mut()
+            .filter(|m| m.license.is_none() && is_us_call(&m.callsign))
+        {
+            if let Some(entry) = self.cache.get(&member.callsign)
+                && now - entry.checked_at <= self.ttl
+            {
+                member.license = Some(entry.to_license());
+                continue;
+            }
+            if failed || lookups >= self.max_lookups {
+                deferred += 1;
+                continue;
+            }
+
+            lookups += 1;
+            match self.client.lookup(&member.callsign).await {
+                Ok(Some(license)) => {
+                    self.cache.insert(
+                        member.callsign.clone(),
+                        CallookEntry {
+                            expires: license.expires,
+                            cancelled: license.cancelled,
+                            checked_at: now,
+                        },
+                    );
+                    self.dirty = true;
+                    member.license = Some(license);
+                }
+                Ok(None) => debug!(
+                    "[{}] callook has no answer for {}",
+                    org_name, member.callsign
+                ),
+                Err(e) => {
+                    warn!(
+                        "[{}] callook lookup failed for {}: {:#}; skipping the rest this cycle",
+                        org_name, member.callsign, e
+                    );
+                    failed = true;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        if lookups > 0 || deferred > 0 {
+            info!(
+                "[{}] callook license checks: {} lookups, {} deferred",
+                org_name, lookups, deferred
+            );
+        }
+    }
+
+    /// Write the callook cache back if anything was looked up.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let content =
+            serde_json::to_string_pretty(&self.cache).context("Failed to serialize cache")?;
+        write_atomic(&self.cache_path, content.as_bytes())
+            .context("Failed to write license cache")?;
+
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl CallookEntry {
+    fn to_license(&self) -> License {
+        License {
+            expires: self.expires,
+            cancelled: self.cancelled,
+            source: "callook",
+        }
+    }
+}
+
+/// Render the members with a license problem as CSV (for `.csv` paths) or
+/// Markdown, sorted by callsign.
+pub fn report(label: &str, path: &str, members: &[Member]) -> Result<String> {
+    let mut flagged: Vec<&Member> = members
+        .iter()
+        .filter(|m| m.license_problem.is_some())
+        .collect();
+    flagged.sort_by(|a, b| a.callsign.cmp(&b.callsign));
+
+    let rows = flagged
+        .into_iter()
+        .map(|m| {
+            let license = m.license.as_ref();
+            vec![
+                m.member_id.clone(),
+                m.callsign.clone(),
+                m.nickname.clone().unwrap_or_default(),
+                m.license_problem.map(|p| p.to_string()).unwrap_or_default(),
+                license
+                    .and_then(|l| l.expires)
+                    .map(|d| d.to_string())
+                    .unwrap_or_default(),
+                license.map(|l| l.source.to_string()).unwrap_or_default(),
+            ]
+        })
+        .collect();
+    render_table(
+        path,
+        &format!("{} License Review", label),
+        "Members whose license is expired or no longer in the licensing database. \
+         Some may be silent keys; please check before removing anyone from the roster.",
+        &[
+            ("member_id", "Member"),
+            ("callsign", "Callsign"),
+            ("name", "Name"),
+            ("status", "Status"),
+            ("expires", "Expires"),
+            ("source", "Source"),
+        ],
+        rows,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_problem_and_grace() {
+        let today = date(2026, 10, 1);
+        let license = License::from_qrz(date(2026, 9, 1));
+        assert_eq!(license.problem(today, 0), Some(LicenseProblem::Expired));
+        assert_eq!(license.problem(today, 60), None);
+        assert_eq!(License::from_qrz(date(2030, 1, 1)).problem(today, 0), None);
+
+        let cancelled = License {
+            expires: None,
+            cancelled: true,
+            source: "callook",
+        };
+        assert_eq!(cancelled.problem(today, 0), Some(LicenseProblem::Cancelled));
+    }
+
+    #[test]
+    fn test_parse_callook() {
+        let valid = r#"{"status":"VALID","type":"PERSON","current":{"callsign":"W1AW"},
+            "otherInfo":{"grantDate":"04/14/2020","expiryDate":"06/01/2030"}}"#;
+        assert_eq!(
+            CallookClient::parse(valid).unwrap(),
+            Some(License {
+                expires: Some(date(2030, 6, 1)),
+                cancelled: false,
+                source: "callook",
+            })
+        );
+        let invalid = r#"{"status":"INVALID"}"#;
+        assert!(CallookClient::parse(invalid).unwrap().unwrap().cancelled);
+        assert_eq!(
+            CallookClient::parse(r#"{"status":"UPDATING"}"#).unwrap(),
+            None
+        );
+
+        assert!(is_us_call("W1AW"));
+        assert!(is_us_call("KI7QCF"));
+        assert!(is_us_call("AA6XY"));
+        assert!(!is_us_call("AM1A"));
+        assert!(!is_us_call("VK1AO"));
+    }
+
+    #[tokio::test]
+    async fn test_fill_from_cache_and_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("license_cache.json");
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"K1OLD": {{"expires": null, "cancelled": true, "checked_at": "{}"}}}}"#,
+                Utc::now().to_rfc3339()
+            ),
+        )
+        .unwrap();
+
+        // No lookups allowed: everything has to come from the cache.
+        let mut checker = LicenseChecker::load(&path, Duration::days(30), 0).unwrap();
+        let mut members = vec![
+            Member::new("K1OLD".to_string(), "7".to_string()),
+            Member::new("DL1ABC".to_string(), "8".to_string()),
+        ];
+        checker.fill("cwops", &mut members).await;
+        assert!(members[0].license.as_ref().unwrap().cancelled);
+        assert_eq!(members[1].license, None);
+
+        members[0].license_problem = Some(LicenseProblem::Cancelled);
+        let md = report("CWops", "licenses.md", &members).unwrap();
+        assert!(md.contains("| 7 | K1OLD |  | cancelled |  | callook |"));
+        assert!(!md.contains("DL1ABC"));
+    }
+}
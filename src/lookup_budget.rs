@@ -0,0 +1,166 @@
+//! Per-cycle and per-day limits on callbook lookups.
+//!
+//! A cold cache (a new SKCC roster, or a wiped cache file) would otherwise
+//! fire thousands of QRZ lookups in a single cycle and burn through the
+//! account's daily quota. The budget caps how many uncached callsigns are
+//! looked up per sync cycle and per UTC day; whatever doesn't fit is
+//! deferred to later cycles.
+//!
+//! The daily count is tracked locally and reconciled with QRZ's own
+//! `<Count>` whenever a response reports it, so a daemon restart doesn't
+//! reset the day's usage to zero.
+
+use chrono::NaiveDate;
+
+#[derive(Debug, Clone)]
+pub struct LookupBudget {
+    per_cycle: Option<usize>,
+    per_day: Option<u64>,
+    day: NaiveDate,
+    used_today: u64,
+    used_this_cycle: usize,
+}
+
+impl LookupBudget {
+    /// Create a budget. `None` means no limit for that window.
+    pub fn new(per_cycle: Option<usize>, per_day: Option<u64>, today: NaiveDate) -> Self {
+        Self {
+            per_cycle,
+            per_day,
+            day: today,
+            used_today: 0,
+            used_this_cycle: 0,
+        }
+    }
+
+    /// Reset the per-cycle count, and the daily count if the day rolled over.
+    pub fn start_cycle(&mut self, today: NaiveDate) {
+        if today != self.day {
+            self.day = today;
+            self.used_today = 0;
+        }
+        self.used_this_cycle = 0;
+    }
+
+    /// Reconcile with the provider's own count of lookups in the last 24h,
+    /// as reported on UTC day `seen`. A count from before the current day
+    /// is stale (the provider only refreshes it on the next lookup) and is
+    /// ignored, or it would carry yesterday's usage into today.
+    pub fn observe_remote_count(&mut self, count: u64, seen: NaiveDate) {
+        if seen == self.day {
+            self.used_today = self.used_today.max(count);
+        }
+    }
+
+    /// Lookups still allowed in this cycle, or `None` when unlimited.
+    pub fn remaining(&self) -> Option<usize> {
+        let cycle = self
+            .per_cycle
+            .map(|limit| limit.saturating_sub(self.used_this_cycle));
+        let day = self
+            .per_day
+            .map(|limit| limit.saturating_sub(self.used_today) as usize);
+        match (cycle, day) {
+            (Some(c), Some(d)) => Some(c.min(d)),
+            (c, d) => c.or(d),
+        }
+    }
+
+    /// Reserve up to `wanted` lookups and return how many were granted.
+    pub fn take(&mut self, wanted: usize) -> usize {
+        let granted = match self.remaining() {
+            Some(remaining) => wanted.min(remaining),
+            None => wanted,
+        };
+        self.used_this_cycle += granted;
+        self.used_today += granted as u64;
+        granted
+    }
+
+    /// Give back `unused` lookups from an earlier [`take`](Self::take) that
+    /// were never made (e.g. skipped while the circuit breaker was open).
+    pub fn refund(&mut self, unused: usize) {
+        self.used_this_cycle = self.used_this_cycle.saturating_sub(unused);
+        self.used_today = self.used_today.saturating_sub(unused as u64);
+    }
+
+    pub fn used_today(&self) -> u64 {
+        self.used_today
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 5, d).unwrap()
+    }
+
+    #[test]
+    fn test_unlimited_budget_grants_everything() {
+        let mut budget = LookupBudget::new(None, None, day(1));
+        assert_eq!(budget.remaining(), None);
+        assert_eq!(budget.take(5000), 5000);
+    }
+
+    #[test]
+    fn test_per_cycle_limit_resets_each_cycle() {
+        let mut budget = LookupBudget::new(Some(100), None, day(1));
+        assert_eq!(budget.take(80), 80);
+        assert_eq!(budget.take(80), 20);
+        assert_eq!(budget.take(1), 0);
+
+        budget.start_cycle(day(1));
+        assert_eq!(budget.take(80), 80);
+    }
+
+    #[test]
+    fn test_per_day_limit_spans_cycles_and_resets_at_midnight() {
+        let mut budget = LookupBudget::new(Some(100), Some(150), day(1));
+        assert_eq!(budget.take(100), 100);
+
+        budget.start_cycle(day(1));
+        assert_eq!(budget.take(100), 50);
+
+        budget.start_cycle(day(2));
+        assert_eq!(budget.used_today(), 0);
+        assert_eq!(budget.take(100), 100);
+    }
+
+    #[test]
+    fn test_refund_returns_unused_lookups() {
+        let mut budget = LookupBudget::new(Some(100), Some(150), day(1));
+        assert_eq!(budget.take(100), 100);
+        budget.refund(40);
+        assert_eq!(budget.used_today(), 60);
+        assert_eq!(budget.take(100), 40);
+    }
+
+    #[test]
+    fn test_remote_count_consumes_daily_budget() {
+        let mut budget = LookupBudget::new(None, Some(1000), day(1));
+        budget.observe_remote_count(990, day(1));
+        assert_eq!(budget.remaining(), Some(10));
+
+        // A lower remote count never gives lookups back.
+        budget.observe_remote_count(5, day(1));
+        assert_eq!(budget.take(50), 10);
+    }
+
+    #[test]
+    fn test_stale_remote_count_ignored_after_rollover() {
+        let mut budget = LookupBudget::new(None, Some(1000), day(1));
+        budget.observe_remote_count(1000, day(1));
+        assert_eq!(budget.remaining(), Some(0));
+
+        // Yesterday's count is still the latest one seen, since no lookup
+        // has run since; it mustn't use up the new day's budget.
+        budget.start_cycle(day(2));
+        budget.observe_remote_count(1000, day(1));
+        assert_eq!(budget.remaining(), Some(1000));
+
+        budget.observe_remote_count(20, day(2));
+        assert_eq!(budget.remaining(), Some(980));
+    }
+}
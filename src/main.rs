@@ -1,15 +1,34 @@
 use anyhow::Result;
-use clap::Parser;
+use chrono::Utc;
+use clap::{Parser, Subcommand};
 use futures::stream::{self, StreamExt};
-use qrqcrew_notes_daemon::nickname_cache::CachedLookup;
-use qrqcrew_notes_daemon::qrz::QrzInfo;
+use qrqcrew_notes_daemon::call_history;
+use qrqcrew_notes_daemon::circuit_breaker::{self, CircuitBreaker};
+use qrqcrew_notes_daemon::combined_notes::{self, CombinedNotes};
+use qrqcrew_notes_daemon::config::{
+    CacheBackend, CombinedCallHistoryConfig, EnrichmentConfig, LicensePolicy, NicknamePriority,
+    Organization, QrzConfig,
+};
+use qrqcrew_notes_daemon::csv_fetcher::{ColumnRef, Source};
+use qrqcrew_notes_daemon::formats;
+use qrqcrew_notes_daemon::license::{self, License};
+use qrqcrew_notes_daemon::names::{clean_name, same_person};
+use qrqcrew_notes_daemon::nickname_cache::{CacheRecord, CacheTtl, CachedLookup};
+use qrqcrew_notes_daemon::notes_generator::Header;
+use qrqcrew_notes_daemon::output_state::OutputState;
+use qrqcrew_notes_daemon::qrz::{QrzError, QrzInfo};
+use qrqcrew_notes_daemon::redact::{self, RedactingMakeWriter};
+use qrqcrew_notes_daemon::scp;
+use qrqcrew_notes_daemon::typos::{self, KnownCalls};
 use qrqcrew_notes_daemon::{
-    Config, CsvFetcher, GitHubClient, GitHubTarget, HtmlFetcher, Member, NicknameCache,
-    NotesGenerator, PendingFile, QrzClient,
+    Config, CsvFetcher, DxccResolver, GitHubClient, GitHubTarget, HtmlFetcher, LicenseChecker,
+    LookupBudget, Member, NicknameCache, Overrides, PendingFile, QrzClient, RejectedRemap,
+    RemapHistory,
 };
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::path::PathBuf;
+use std::process::ExitCode;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::TcpStream;
@@ -32,19 +51,75 @@ struct Cli {
     /// Dry run - don't commit to GitHub
     #[arg(long)]
     dry_run: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect or edit the QRZ lookup cache (stop the daemon first when
+    /// using the JSON backend, or it will overwrite your changes)
+    #[command(subcommand)]
+    Cache(CacheCommand),
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// List every cached entry
+    List,
+    /// Show the cached entry for one callsign
+    Get { callsign: String },
+    /// Remove entries so they're looked up again on the next cycle
+    Invalidate {
+        /// Callsign to invalidate
+        #[arg(required_unless_present_any = ["all_not_found", "older_than"])]
+        callsign: Option<String>,
+        /// Invalidate every not-found entry
+        #[arg(long)]
+        all_not_found: bool,
+        /// Invalidate entries cached more than this many days ago
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<i64>,
+    },
+    /// Remove every entry past its TTL
+    Prune,
+    /// Write the cache to a file (.csv for CSV, anything else for JSON)
+    Export { path: PathBuf },
+    /// Load entries from a file (.csv for CSV, anything else for JSON)
+    Import { path: PathBuf },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("qrqcrew_notes_daemon=info".parse()?),
+                .add_directive("qrqcrew_notes_daemon=info".parse().unwrap()),
         )
+        .with_writer(RedactingMakeWriter::new(std::io::stdout))
         .init();
 
+    // Errors that end the process bypass tracing, so redact them here.
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", redact::error_chain(&e));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run() -> Result<()> {
     let cli = Cli::parse();
     let config = Config::load(Some(cli.config))?;
+    for secret in config.secrets() {
+        redact::register_secret(secret);
+    }
+
+    if let Some(Command::Cache(command)) = cli.command {
+        return run_cache_command(command, config.qrz.as_ref());
+    }
 
     let run_once = cli.once || config.daemon.run_once;
 
@@ -64,10 +139,11 @@ async fn main() -> Result<()> {
     let qrz_client = match &config.qrz {
         Some(qrz_config) if qrz_config.enabled => {
             info!("QRZ lookups enabled");
-            Some(QrzClient::new(
-                qrz_config.username.clone(),
-                qrz_config.password.clone(),
-            ))
+            let client = QrzClient::new(qrz_config.username.clone(), qrz_config.password.clone());
+            Some(match qrz_config.subscription_warning_days {
+                Some(days) => client.with_expiry_warning_days(days),
+                None => client,
+            })
         }
         Some(_) => {
             info!("QRZ lookups disabled in config");
@@ -78,64 +154,206 @@ async fn main() -> Result<()> {
             None
         }
     };
+    if qrz_client.is_some() {
+        for org in &enabled_orgs {
+            info!("[{}] QRZ enrichment: {}", org.name, org.enrichment);
+        }
+    }
+
+    // DXCC country file, if configured. Missing entity data only affects
+    // the flags, so a bad file is logged and otherwise ignored.
+    let dxcc = config
+        .dxcc_file
+        .as_deref()
+        .and_then(|path| match DxccResolver::load(path) {
+            Ok(resolver) => Some(resolver),
+            Err(e) => {
+                warn!(
+                    "Failed to load DXCC country file: {}",
+                    redact::error_chain(&e)
+                );
+                None
+            }
+        });
+
+    // Remap history, for the callsign change reports
+    let history_path = config
+        .remap_history_file
+        .clone()
+        .unwrap_or_else(|| "remap_history.json".to_string());
+    // A corrupt history is fatal rather than silently overwritten.
+    let mut remap_history = RemapHistory::load(&history_path)?;
+
+    // Content hashes behind the Generated: timestamps. Losing them only
+    // bumps the timestamps once, so a bad file is replaced.
+    let state_path = config
+        .output_state_file
+        .clone()
+        .unwrap_or_else(|| "output_state.json".to_string());
+    let mut output_state = OutputState::load(&state_path).unwrap_or_else(|e| {
+        warn!(
+            "Failed to load output state, starting fresh: {}",
+            redact::error_chain(&e)
+        );
+        OutputState::new(&state_path)
+    });
+
+    // callook.info license checks, if configured. A cache that can't be
+    // read disables them rather than being overwritten.
+    let mut license_checker = config.license.as_ref().filter(|l| l.callook).and_then(|l| {
+        let path = l.cache_path.as_deref().unwrap_or("license_cache.json");
+        let ttl =
+            chrono::Duration::days(l.cache_ttl_days.unwrap_or(DEFAULT_LICENSE_CACHE_TTL_DAYS));
+        let max_lookups = l
+            .max_lookups_per_cycle
+            .unwrap_or(DEFAULT_MAX_CALLOOK_LOOKUPS);
+        match LicenseChecker::load(path, ttl, max_lookups) {
+            Ok(checker) => Some(checker),
+            Err(e) => {
+                warn!(
+                    "Failed to load license cache, skipping callook checks: {}",
+                    redact::error_chain(&e)
+                );
+                None
+            }
+        }
+    });
+
+    let ctx = SyncContext {
+        github: &config.github,
+        overrides_file: config.overrides_file.as_deref(),
+        dxcc: dxcc.as_ref(),
+        call_history: config.call_history.as_ref(),
+        license_grace_days: config
+            .license
+            .as_ref()
+            .and_then(|l| l.grace_days)
+            .unwrap_or(0),
+        dry_run: cli.dry_run,
+    };
 
     // Persistent nickname cache (survives daemon restarts)
-    let cache_path = config
-        .qrz
-        .as_ref()
-        .and_then(|q| q.cache_path.clone())
-        .unwrap_or_else(|| "nickname_cache.json".to_string());
-
-    let nickname_cache = Arc::new(RwLock::new(
-        NicknameCache::load(&cache_path).unwrap_or_else(|e| {
-            warn!("Failed to load nickname cache: {}, starting fresh", e);
-            NicknameCache::load("/dev/null").unwrap() // Empty cache
-        }),
-    ));
+    let nickname_cache = Arc::new(RwLock::new(open_cache(config.qrz.as_ref())));
+
+    let mut qrz_lookups = qrz_client.map(|client| {
+        let qrz_config = config.qrz.as_ref();
+        QrzLookups {
+            client,
+            cache: nickname_cache.clone(),
+            max_concurrent: qrz_config
+                .and_then(|q| q.max_concurrent_lookups)
+                .unwrap_or(DEFAULT_MAX_CONCURRENT_LOOKUPS),
+            max_stale_refreshes: qrz_config
+                .and_then(|q| q.max_stale_refreshes_per_cycle)
+                .unwrap_or(DEFAULT_MAX_STALE_REFRESHES),
+            budget: LookupBudget::new(
+                qrz_config.and_then(|q| q.max_lookups_per_cycle),
+                qrz_config.and_then(|q| q.max_lookups_per_day),
+                Utc::now().date_naive(),
+            ),
+            breaker: CircuitBreaker::new(
+                qrz_config
+                    .and_then(|q| q.breaker_failure_threshold)
+                    .unwrap_or(circuit_breaker::DEFAULT_FAILURE_THRESHOLD),
+                qrz_config
+                    .and_then(|q| q.breaker_cooldown_secs)
+                    .map(Duration::from_secs)
+                    .unwrap_or(circuit_breaker::DEFAULT_COOLDOWN),
+            ),
+        }
+    });
 
     loop {
         // Run connectivity diagnostics before each sync cycle
         run_connectivity_check().await;
 
-        let mut pending_files = Vec::new();
+        if let Some(lookups) = qrz_lookups.as_mut() {
+            lookups.start_cycle().await;
+        }
 
-        // Get max concurrent lookups from config
-        let max_concurrent_lookups = config
-            .qrz
-            .as_ref()
-            .and_then(|q| q.max_concurrent_lookups)
-            .unwrap_or(DEFAULT_MAX_CONCURRENT_LOOKUPS);
+        let mut pending_files = Vec::new();
+        let mut synced = Vec::new();
+        let cycle_start = Utc::now();
 
         for org in &enabled_orgs {
             info!("[{}] Starting sync", org.name);
+            let enrichment = if qrz_lookups.is_some() {
+                org.enrichment.to_string()
+            } else {
+                "QRZ disabled".to_string()
+            };
             match prepare_org_update(
                 org,
-                &config.github,
-                cli.dry_run,
-                &qrz_client,
-                &nickname_cache,
-                max_concurrent_lookups,
+                &ctx,
+                qrz_lookups.as_mut(),
+                license_checker.as_mut(),
+                &mut remap_history,
+                &mut output_state,
+                cycle_start,
             )
             .await
             {
-                Ok(Some(pending)) => {
-                    info!(
-                        "[{}] Prepared update for {} ({} members) -> {}/{}",
-                        org.name,
-                        pending.path,
-                        pending.member_count,
-                        pending.target.owner,
-                        pending.target.repo
-                    );
-                    pending_files.push(pending);
+                Ok(OrgUpdate { files, members }) if !files.is_empty() => {
+                    for file in &files {
+                        info!(
+                            "[{}] Prepared update for {} ({} members, {}) -> {}/{}",
+                            org.name,
+                            file.path,
+                            file.member_count,
+                            enrichment,
+                            file.target.owner,
+                            file.target.repo
+                        );
+                    }
+                    pending_files.extend(files);
+                    synced.push((*org, members));
                 }
-                Ok(None) => {
+                Ok(OrgUpdate { members, .. }) => {
                     info!("[{}] No update needed (dry run or empty roster)", org.name);
+                    if !members.is_empty() {
+                        synced.push((*org, members));
+                    }
                 }
                 Err(e) => {
-                    error!("[{}] Sync failed: {}", org.name, e);
+                    error!("[{}] Sync failed: {}", org.name, redact::error_chain(&e));
+                }
+            }
+        }
+
+        match prepare_combined_update(
+            &config,
+            &synced,
+            &mut output_state,
+            cycle_start,
+            cli.dry_run,
+        ) {
+            Ok(files) => {
+                for file in &files {
+                    info!(
+                        "Prepared update for {} ({} callsigns) -> {}/{}",
+                        file.path, file.member_count, file.target.owner, file.target.repo
+                    );
                 }
+                pending_files.extend(files);
             }
+            Err(e) => error!("Combined outputs failed: {}", redact::error_chain(&e)),
+        }
+
+        if !cli.dry_run
+            && let Err(e) = output_state.save()
+        {
+            warn!("Failed to save output state: {}", redact::error_chain(&e));
+        }
+        if !cli.dry_run
+            && let Err(e) = remap_history.save()
+        {
+            warn!("Failed to save remap history: {}", redact::error_chain(&e));
+        }
+        if !cli.dry_run
+            && let Some(checker) = license_checker.as_mut()
+            && let Err(e) = checker.save()
+        {
+            warn!("Failed to save license cache: {}", redact::error_chain(&e));
         }
 
         // Group pending files by target repository
@@ -155,15 +373,19 @@ async fn main() -> Result<()> {
                         let message = build_commit_message(&files);
                         if let Err(e) = client.batch_commit(&files, &message).await {
                             error!(
-                                "Batch commit to {}/{} failed: {:?}",
-                                target.owner, target.repo, e
+                                "Batch commit to {}/{} failed: {}",
+                                target.owner,
+                                target.repo,
+                                redact::error_chain(&e)
                             );
                         }
                     }
                     Err(e) => {
                         error!(
                             "Failed to create GitHub client for {}/{}: {}",
-                            target.owner, target.repo, e
+                            target.owner,
+                            target.repo,
+                            redact::error_chain(&e)
                         );
                     }
                 }
@@ -199,22 +421,67 @@ fn build_commit_message(files: &[PendingFile]) -> String {
     }
 }
 
-async fn prepare_org_update(
-    org: &qrqcrew_notes_daemon::config::Organization,
-    global_github: &qrqcrew_notes_daemon::config::GitHubConfig,
+/// Settings shared by every org in a sync cycle.
+struct SyncContext<'a> {
+    github: &'a qrqcrew_notes_daemon::config::GitHubConfig,
+    overrides_file: Option<&'a str>,
+    dxcc: Option<&'a DxccResolver>,
+    /// The combined call history, whose roster columns its orgs fetch too
+    call_history: Option<&'a CombinedCallHistoryConfig>,
+    license_grace_days: i64,
     dry_run: bool,
-    qrz_client: &Option<QrzClient>,
-    nickname_cache: &Arc<RwLock<NicknameCache>>,
-    max_concurrent_lookups: usize,
-) -> Result<Option<PendingFile>> {
+}
+
+/// One org's files for the batch commit, and its final member list for the
+/// combined outputs.
+struct OrgUpdate {
+    files: Vec<PendingFile>,
+    members: Vec<Member>,
+}
+
+/// Build one org's notes file, plus whichever reports it has configured.
+/// Returns no files for dry runs and empty rosters.
+async fn prepare_org_update(
+    org: &Organization,
+    ctx: &SyncContext<'_>,
+    mut qrz_lookups: Option<&mut QrzLookups>,
+    license_checker: Option<&mut LicenseChecker>,
+    remap_history: &mut RemapHistory,
+    output_state: &mut OutputState,
+    cycle_start: chrono::DateTime<Utc>,
+) -> Result<OrgUpdate> {
     // Resolve the effective GitHub target (per-org override or global fallback)
-    let target = GitHubTarget::resolve(org.github.as_ref(), global_github);
+    let target = GitHubTarget::resolve(org.github.as_ref(), ctx.github);
+    let nickname_column = org
+        .nickname_column
+        .clone()
+        .map(ColumnRef::Name)
+        .or(org.nickname_column_index.map(ColumnRef::Index));
+    let outputs = formats::for_org(org)?;
+    let sort = org.sort_order()?;
+    let combined_fields = match ctx.call_history {
+        Some(combined) if combined.includes(org) => combined.call_history()?.roster_fields(),
+        _ => Vec::new(),
+    };
+    let mut extra_columns = Vec::new();
+    for field in outputs
+        .iter()
+        .flat_map(|o| o.formatter.roster_fields())
+        .chain(sort.roster_field().map(str::to_string))
+        .chain(combined_fields)
+    {
+        if !extra_columns.contains(&field) {
+            extra_columns.push(field);
+        }
+    }
     // 1. Fetch roster based on source type
     let mut members = match org.source_type.as_str() {
         "html_table" => {
             let callsign_idx = org.callsign_column_index.unwrap_or(1);
             let number_idx = org.number_column_index.unwrap_or(0);
-            let fetcher = HtmlFetcher::new(org.roster_url.clone(), callsign_idx, number_idx);
+            let fetcher = HtmlFetcher::new(org.roster_url.clone(), callsign_idx, number_idx)
+                .with_nickname_column(nickname_column)
+                .with_extra_columns(extra_columns);
             fetcher.fetch_members().await?
         }
         _ => {
@@ -232,7 +499,9 @@ async fn prepare_org_update(
                 callsign_col,
                 number_col,
                 org.skip_rows,
-            );
+            )
+            .with_nickname_column(nickname_column)
+            .with_extra_columns(extra_columns);
             fetcher.fetch_members().await?
         }
     };
@@ -244,49 +513,555 @@ async fn prepare_org_update(
 
     if members.is_empty() {
         warn!("[{}] No members found in roster, skipping", org.name);
-        return Ok(None);
+        return Ok(OrgUpdate {
+            files: Vec::new(),
+            members,
+        });
     }
 
-    // 2. Enrich with nicknames from QRZ if available
-    if let Some(qrz) = qrz_client {
-        enrich_with_nicknames(
-            &mut members,
-            qrz,
-            nickname_cache,
-            &org.name,
-            max_concurrent_lookups,
-        )
-        .await;
+    // 2. Enrich with nicknames from QRZ if available and the org wants it
+    if let Some(lookups) = qrz_lookups.as_deref_mut() {
+        if org.enrichment.needs_lookups() {
+            let roster_nickname_wins = org.nickname_priority == NicknamePriority::Roster;
+            enrich_with_nicknames(
+                &mut members,
+                lookups,
+                &org.name,
+                org.enrichment,
+                roster_nickname_wins,
+                remap_history,
+                cycle_start,
+            )
+            .await;
+        } else {
+            info!("[{}] Skipping QRZ enrichment (disabled for org)", org.name);
+        }
+    }
+
+    // 3. Apply manual overrides (global, then per-org)
+    let overrides = Overrides::merged(
+        ctx.overrides_file.map(Overrides::load).transpose()?,
+        org.overrides_file
+            .as_deref()
+            .map(Overrides::load)
+            .transpose()?,
+    );
+    if !overrides.is_empty() {
+        let applied = overrides.apply(&mut members, &org.name);
+        info!("[{}] Applied {} override(s)", org.name, applied);
+    }
+
+    // Dedupe: if remap produced two rows with the same callsign, keep the
+    // one with the numerically lower member id (typical convention: lower
+    // number = older membership). Stable: input order otherwise preserved.
+    let dropped = dedupe_by_callsign(&mut members);
+    if dropped > 0 {
+        info!(
+            "[{}] Dropped {} duplicate row(s) after callsign remap",
+            org.name, dropped
+        );
+    }
+
+    // Resolve DXCC entities for the final callsigns
+    if let Some(dxcc) = ctx.dxcc {
+        for member in members.iter_mut() {
+            member.dxcc = dxcc.resolve(&member.callsign).cloned();
+        }
+    } else if org.flag_emoji {
+        warn!(
+            "[{}] flag_emoji is set but no dxcc_file is loaded, skipping flags",
+            org.name
+        );
+    }
+
+    // Record remaps for the callsign change report (and date override remaps)
+    remap_history.observe(&org.name, &mut members, cycle_start);
+    let mut reports = Vec::new();
+
+    // Typo suggestions for roster calls QRZ doesn't know
+    if let Some(path) = &org.typo_report_file {
+        match &qrz_lookups {
+            Some(lookups) => {
+                let cache = lookups.cache.read().await;
+                let not_found = typos::check(&members, &cache, &KnownCalls::from_cache(&cache));
+                info!(
+                    "[{}] {} roster callsign(s) not found on QRZ",
+                    org.name,
+                    not_found.len()
+                );
+                let content = typos::report(&org.label, path, &not_found)?;
+                reports.push((path, "callsigns not found", content, not_found.len()));
+            }
+            None => warn!(
+                "[{}] typo_report_file is set but QRZ is disabled, skipping",
+                org.name
+            ),
+        }
+    }
+
+    // License checks: fill in what QRZ didn't have, then report, flag or
+    // exclude expired and cancelled licenses per the org's policy.
+    if org.license_policy != LicensePolicy::Keep || org.license_report_file.is_some() {
+        if let Some(checker) = license_checker {
+            checker.fill(&org.name, &mut members).await;
+        }
+        let today = cycle_start.date_naive();
+        for member in members.iter_mut() {
+            member.license_problem = member
+                .license
+                .as_ref()
+                .and_then(|l| l.problem(today, ctx.license_grace_days));
+        }
+        let problems = members
+            .iter()
+            .filter(|m| m.license_problem.is_some())
+            .count();
+        info!(
+            "[{}] License check: {} member(s) expired or cancelled (policy: {})",
+            org.name, problems, org.license_policy
+        );
+        if let Some(path) = &org.license_report_file {
+            let content = license::report(&org.label, path, &members)?;
+            reports.push((path, "license review", content, problems));
+        }
+        if org.license_policy == LicensePolicy::Exclude {
+            members.retain(|m| m.license_problem.is_none());
+        }
+    }
+
+    // 4. Render each output (notes file, call history, exports, ...), one
+    // file per shard for sharded outputs
+    let mut rendered = Vec::new();
+    for output in &outputs {
+        let label = match output.formatter.kind() {
+            Some(kind) => format!("{} {}", org.label, kind),
+            None => org.label.clone(),
+        };
+        let Some(shards) = &output.shards else {
+            let key = format!("{}/{}/{}", target.owner, target.repo, output.path);
+            let content = output_state.render(&key, cycle_start, |generated| {
+                output.formatter.format(&members, generated)
+            })?;
+            rendered.push((output.path.clone(), label, content, members.len()));
+            continue;
+        };
+        // Each shard renders from the whole member list, so lines are
+        // placed and checked against every member, not just the shard's
+        let mut listed = Vec::new();
+        for (shard, path) in shards.files() {
+            let in_shard =
+                |callsign: &str, member: &Member| shards.contains(&shard, callsign, member);
+            let count = members.iter().filter(|m| in_shard(&m.callsign, m)).count();
+            let key = format!("{}/{}/{}", target.owner, target.repo, path);
+            let content = output_state.render(&key, cycle_start, |generated| {
+                output
+                    .formatter
+                    .format_shard(&members, &in_shard, generated)
+            })?;
+            listed.push((path.clone(), count));
+            rendered.push((path, label.clone(), content, count));
+        }
+        let content = shards.index(&label, &listed)?;
+        rendered.push((
+            shards.index_path().to_string(),
+            format!("{} shard index", label),
+            content,
+            members.len(),
+        ));
+    }
+
+    if let Some(path) = &org.remap_report_file {
+        let content = remap_history.report(&org.name, &org.label, path, cycle_start)?;
+        let count = remap_history.records(&org.name).len();
+        reports.push((path, "callsign changes", content, count));
+    }
+    if let Some(path) = &org.remap_review_file {
+        let content = remap_history.review_report(&org.name, &org.label, path, cycle_start)?;
+        let count = remap_history.rejections(&org.name).len();
+        reports.push((path, "remaps needing review", content, count));
+    }
+
+    if ctx.dry_run {
+        for (path, _, content, _) in &rendered {
+            info!(
+                "[{}] Dry run - would write {}:\n{}",
+                org.name, path, content
+            );
+        }
+        for (path, _, report, _) in &reports {
+            info!("[{}] Dry run - would write {}:\n{}", org.name, path, report);
+        }
+        return Ok(OrgUpdate {
+            files: Vec::new(),
+            members,
+        });
+    }
+
+    // 5. Return pending files for batch commit
+    let mut files = Vec::new();
+    for (path, org_label, content, member_count) in rendered {
+        files.push(PendingFile {
+            path,
+            content,
+            org_label,
+            member_count,
+            target: target.clone(),
+        });
+    }
+    for (path, kind, content, count) in reports {
+        files.push(PendingFile {
+            path: path.clone(),
+            content,
+            org_label: format!("{} {}", org.label, kind),
+            member_count: count,
+            target: target.clone(),
+        });
+    }
+    Ok(OrgUpdate { files, members })
+}
+
+/// Build the outputs that combine several orgs, from the final member lists
+/// of the orgs synced this cycle. An output is skipped when one of its orgs
+/// has no members this cycle (failed, empty or disabled), rather than
+/// published without them.
+fn prepare_combined_update(
+    config: &Config,
+    synced: &[(&Organization, Vec<Member>)],
+    output_state: &mut OutputState,
+    cycle_start: chrono::DateTime<Utc>,
+    dry_run: bool,
+) -> Result<Vec<PendingFile>> {
+    let target = GitHubTarget::resolve(None, &config.github);
+    // (path, label, content, callsign count)
+    let mut outputs = Vec::new();
+    let skip = |path: &str, missing: &str| {
+        warn!(
+            "Skipping {}: org {} has no members this cycle",
+            path, missing
+        );
+    };
+
+    if let Some(combined) = &config.call_history {
+        match combined_orgs(combined.organizations.as_deref(), config, synced) {
+            Ok(orgs) => {
+                let mut entries: Vec<call_history::Entry> = orgs
+                    .iter()
+                    .flat_map(|(org, members)| {
+                        members.iter().map(|member| call_history::Entry {
+                            member,
+                            emoji: &org.emoji,
+                            label: &org.label,
+                        })
+                    })
+                    .collect();
+                // Stable, so a shared callsign keeps the first org's row
+                entries.sort_by(|a, b| a.member.callsign.cmp(&b.member.callsign));
+                let labels: Vec<&str> = orgs.iter().map(|(org, _)| org.label.as_str()).collect();
+                let title = format!("Call history: {}", labels.join(", "));
+                let content = combined.call_history()?.generate(&title, &entries);
+                let callsigns: HashSet<&str> =
+                    entries.iter().map(|e| e.member.callsign.as_str()).collect();
+                outputs.push((
+                    &combined.file,
+                    "Combined call history",
+                    content,
+                    callsigns.len(),
+                ));
+            }
+            Err(missing) => skip(&combined.file, missing),
+        }
     }
 
-    // 3. Generate notes file
-    let generator = NotesGenerator::new(org.emoji.clone(), org.label.clone(), None);
-    let content = generator.generate(&members);
+    if let Some(combined) = &config.combined_notes {
+        match combined_orgs(combined.organizations.as_deref(), config, synced) {
+            Ok(orgs) => {
+                let org_members: Vec<combined_notes::OrgMembers> = orgs
+                    .iter()
+                    .map(|(org, members)| combined_notes::OrgMembers {
+                        emoji: &org.emoji,
+                        label: &org.label,
+                        members,
+                    })
+                    .collect();
+                let names: Vec<&str> = orgs.iter().map(|(org, _)| org.name.as_str()).collect();
+                let header = Header::from_config(
+                    &combined.header,
+                    vec![("org".to_string(), names.join(", "))],
+                );
+                let mut notes = CombinedNotes::new(
+                    combined.label.clone().unwrap_or_else(|| "Club".to_string()),
+                    combined.header.url.clone(),
+                )
+                .with_nickname_priority(combined.nickname_priority)
+                .with_header(header);
+                if let Some(separator) = &combined.separator {
+                    notes = notes.with_separator(separator.clone());
+                }
+                let key = format!("{}/{}/{}", target.owner, target.repo, combined.file);
+                let content = output_state.render(&key, cycle_start, |generated| {
+                    Ok(notes.generate_at(&org_members, generated))
+                })?;
+                let count = content
+                    .lines()
+                    .filter(|l| !l.starts_with('#') && !l.is_empty())
+                    .count();
+                outputs.push((&combined.file, "Combined notes", content, count));
+            }
+            Err(missing) => skip(&combined.file, missing),
+        }
+    }
+
+    if let Some(scp_config) = &config.scp {
+        match combined_orgs(None, config, synced) {
+            Ok(orgs) => {
+                let callsigns = orgs
+                    .iter()
+                    .flat_map(|(_, members)| members.iter().map(|m| m.callsign.as_str()));
+                let content = scp::generate(&scp_config.header, callsigns);
+                let count = content.lines().filter(|l| !l.starts_with('#')).count();
+                outputs.push((&scp_config.file, "Super Check Partial", content, count));
+            }
+            Err(missing) => skip(&scp_config.file, missing),
+        }
+    }
 
     if dry_run {
-        info!("[{}] Dry run - would generate:\n{}", org.name, content);
-        return Ok(None);
-    }
-
-    // 4. Return pending file for batch commit
-    Ok(Some(PendingFile {
-        path: org.output_file.clone(),
-        content,
-        org_label: org.label.clone(),
-        member_count: members.len(),
-        target,
-    }))
+        for (path, _, content, _) in &outputs {
+            info!("Dry run - would write {}:\n{}", path, content);
+        }
+        return Ok(Vec::new());
+    }
+    Ok(outputs
+        .into_iter()
+        .map(|(path, label, content, count)| PendingFile {
+            path: path.clone(),
+            content,
+            org_label: label.to_string(),
+            member_count: count,
+            target: target.clone(),
+        })
+        .collect())
+}
+
+/// The synced orgs a combined output includes, in its order: the named
+/// orgs, or every enabled org. Errors with the name of an org that didn't
+/// sync.
+fn combined_orgs<'a>(
+    names: Option<&'a [String]>,
+    config: &'a Config,
+    synced: &'a [(&'a Organization, Vec<Member>)],
+) -> Result<Vec<&'a (&'a Organization, Vec<Member>)>, &'a str> {
+    let names: Vec<&str> = match names {
+        Some(names) => names.iter().map(String::as_str).collect(),
+        None => config
+            .organizations
+            .iter()
+            .filter(|o| o.enabled)
+            .map(|o| o.name.as_str())
+            .collect(),
+    };
+    names
+        .into_iter()
+        .map(|name| synced.iter().find(|(org, _)| org.name == name).ok_or(name))
+        .collect()
 }
 
 /// Default max concurrent QRZ lookups
 const DEFAULT_MAX_CONCURRENT_LOOKUPS: usize = 10;
 
+/// Default max stale cache entries refreshed per org per cycle
+const DEFAULT_MAX_STALE_REFRESHES: usize = 100;
+
+/// Default days a callook.info result is reused
+const DEFAULT_LICENSE_CACHE_TTL_DAYS: i64 = 30;
+
+/// Default max callook.info lookups per org per cycle
+const DEFAULT_MAX_CALLOOK_LOOKUPS: usize = 100;
+
+/// Open the configured cache backend. Falls back to an in-memory cache
+/// (leaving the file on disk untouched) if it can't be opened.
+fn open_cache(qrz: Option<&QrzConfig>) -> NicknameCache {
+    try_open_cache(qrz).unwrap_or_else(|e| {
+        warn!(
+            "Failed to load nickname cache: {:#}, starting fresh (in memory only)",
+            e
+        );
+        NicknameCache::in_memory(cache_ttl(qrz))
+    })
+}
+
+fn try_open_cache(qrz: Option<&QrzConfig>) -> Result<NicknameCache> {
+    let ttl = cache_ttl(qrz);
+    let sqlite = qrz.is_some_and(|q| q.cache_backend == CacheBackend::Sqlite);
+    let cache_path = qrz.and_then(|q| q.cache_path.clone()).unwrap_or_else(|| {
+        if sqlite {
+            "nickname_cache.db".to_string()
+        } else {
+            "nickname_cache.json".to_string()
+        }
+    });
+
+    if sqlite {
+        NicknameCache::open_sqlite(&cache_path, ttl).and_then(|mut cache| {
+            if let Some(import_path) = qrz.and_then(|q| q.import_cache_path.as_ref())
+                && cache.is_empty()
+                && std::path::Path::new(import_path).exists()
+            {
+                let imported = cache.import_json(import_path)?;
+                info!(
+                    "Imported {} entries from {} into {}",
+                    imported, import_path, cache_path
+                );
+            }
+            Ok(cache)
+        })
+    } else {
+        NicknameCache::load_with_ttl(&cache_path, ttl)
+    }
+}
+
+/// Run a `cache` subcommand against the configured cache and exit.
+fn run_cache_command(command: CacheCommand, qrz: Option<&QrzConfig>) -> Result<()> {
+    let mut cache = try_open_cache(qrz)?;
+
+    match command {
+        CacheCommand::List => {
+            let records = cache.records();
+            print_cache_records(&records);
+            println!("{} entries", records.len());
+        }
+        CacheCommand::Get { callsign } => match cache.record(&callsign) {
+            Some(record) => {
+                print_cache_records(std::slice::from_ref(&record));
+                if let Some(since) = record.remapped_since {
+                    println!("remapped since {}", since.format("%Y-%m-%d"));
+                }
+            }
+            None => println!("{} is not cached", callsign.to_uppercase()),
+        },
+        CacheCommand::Invalidate {
+            callsign,
+            all_not_found,
+            older_than,
+        } => {
+            let cutoff = older_than.map(|days| Utc::now() - chrono::Duration::days(days));
+            let mut targets: Vec<String> = cache
+                .records()
+                .into_iter()
+                .filter(|r| {
+                    (all_not_found && r.hit.lookup == CachedLookup::NotFound)
+                        || cutoff.is_some_and(|cutoff| r.cached_at < cutoff)
+                })
+                .map(|r| r.callsign)
+                .collect();
+            if let Some(callsign) = callsign {
+                if cache.record(&callsign).is_none() {
+                    println!("{} is not cached", callsign.to_uppercase());
+                }
+                targets.push(callsign.to_uppercase());
+            }
+
+            let mut removed = 0;
+            for callsign in &targets {
+                if cache.remove(callsign)? {
+                    removed += 1;
+                }
+            }
+            cache.save()?;
+            println!("Invalidated {} entries", removed);
+        }
+        CacheCommand::Prune => {
+            let pruned = cache.prune_expired()?;
+            cache.save()?;
+            println!("Pruned {} expired entries", pruned.len());
+        }
+        CacheCommand::Export { path } => {
+            let count = cache.export(&path)?;
+            println!("Exported {} entries to {}", count, path.display());
+        }
+        CacheCommand::Import { path } => {
+            let count = cache.import(&path)?;
+            cache.save()?;
+            println!("Imported {} entries from {}", count, path.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn print_cache_records(records: &[CacheRecord]) {
+    println!(
+        "{:<10} {:<10} {:<12} {:<6} {:<10} EXPIRES",
+        "CALLSIGN", "CURRENT", "NICKNAME", "STATE", "CACHED"
+    );
+    for record in records {
+        let (current, nickname) = match &record.hit.lookup {
+            CachedLookup::Found {
+                current_call,
+                nickname,
+            } => (current_call.as_str(), nickname.as_deref().unwrap_or("-")),
+            CachedLookup::NotFound => ("(none)", "-"),
+        };
+        let state = if record.legacy {
+            "legacy"
+        } else if record.hit.fresh {
+            "fresh"
+        } else {
+            "stale"
+        };
+        println!(
+            "{:<10} {:<10} {:<12} {:<6} {:<10} {}",
+            record.callsign,
+            current,
+            nickname,
+            state,
+            record.cached_at.format("%Y-%m-%d"),
+            record.expires_at.format("%Y-%m-%d"),
+        );
+    }
+}
+
+/// Build the cache expiry policy from `[qrz]`, falling back to defaults.
+fn cache_ttl(qrz: Option<&QrzConfig>) -> CacheTtl {
+    let defaults = CacheTtl::default();
+    let days = |v: Option<i64>, default| v.map(chrono::Duration::days).unwrap_or(default);
+    CacheTtl {
+        found: days(qrz.and_then(|q| q.found_ttl_days), defaults.found),
+        not_found: days(qrz.and_then(|q| q.not_found_ttl_days), defaults.not_found),
+        jitter: days(qrz.and_then(|q| q.ttl_jitter_days), defaults.jitter),
+        max_stale: days(qrz.and_then(|q| q.max_stale_days), defaults.max_stale),
+    }
+}
+
+/// QRZ lookup state shared by every org in a cycle. The budget and breaker
+/// are carried across cycles so the daily limit and cooldown hold.
+struct QrzLookups {
+    client: QrzClient,
+    cache: Arc<RwLock<NicknameCache>>,
+    max_concurrent: usize,
+    max_stale_refreshes: usize,
+    budget: LookupBudget,
+    breaker: CircuitBreaker,
+}
+
+impl QrzLookups {
+    async fn start_cycle(&mut self) {
+        self.budget.start_cycle(Utc::now().date_naive());
+        let status = self.client.session_status().await;
+        if let (Some(count), Some(seen)) = (status.count, status.count_day) {
+            self.budget.observe_remote_count(count, seen);
+        }
+    }
+}
+
 /// One QRZ result keyed by the queried (roster) callsign.
 #[derive(Debug, Clone)]
 enum LookupResult {
     Found(QrzInfo),
     NotFound,
     Error,
+    /// Not attempted because the circuit breaker was open.
+    Skipped,
 }
 
 /// Enrich members with QRZ data (current callsign + nickname).
@@ -294,54 +1069,124 @@ enum LookupResult {
 /// For every member: look up QRZ once. Apply the canonical `<call>` back
 /// onto the member (so retired/aliased roster entries become the operator's
 /// current callsign in the generated PoLo notes), and apply `<fname>` as
-/// the nickname. After remapping, dedupe by current callsign — if two
-/// roster rows resolve to the same operator, the lower member number wins.
+/// the nickname. Rows that now share a callsign are left for the caller to
+/// dedupe once overrides have been applied.
+///
+/// Expired cache entries are still applied (stale-while-revalidate), and up
+/// to `max_stale_refreshes` of them are looked up again each cycle.
+///
+/// Uncached lookups are limited by the lookup budget. Callsigns never seen
+/// before go first, then stale refreshes; the rest are deferred to a later
+/// cycle and keep their cached (or roster) data in the meantime. While the
+/// circuit breaker is open, remaining lookups are skipped and only cached
+/// data is applied.
+///
+/// `policy` controls which parts of the result are applied: orgs whose
+/// roster is authoritative for the callsign turn remaps off, and orgs that
+/// don't want QRZ names turn nicknames off. QRZ names go through the same
+/// cleanup as roster names, and a name already taken from the roster is
+/// only replaced when `roster_nickname_wins` is false.
 async fn enrich_with_nicknames(
-    members: &mut Vec<Member>,
-    qrz: &QrzClient,
-    cache: &Arc<RwLock<NicknameCache>>,
+    members: &mut [Member],
+    lookups: &mut QrzLookups,
     org_name: &str,
-    max_concurrent: usize,
+    policy: EnrichmentConfig,
+    roster_nickname_wins: bool,
+    remap_history: &mut RemapHistory,
+    now: chrono::DateTime<Utc>,
 ) {
+    let QrzLookups {
+        client: qrz,
+        cache,
+        max_concurrent,
+        max_stale_refreshes,
+        budget,
+        breaker,
+    } = lookups;
+    let max_concurrent = *max_concurrent;
+    let breaker = &*breaker;
+
     // Collect roster callsigns and split into cached / uncached.
     let queried_callsigns: Vec<String> = members.iter().map(|m| m.callsign.clone()).collect();
 
     let mut cache_hits = 0;
     let mut cached_results: HashMap<String, LookupResult> = HashMap::new();
+    // First names cached while a roster call was still its operator's
+    // current call: the identity to check remaps against.
+    let mut prior_names: HashMap<String, String> = HashMap::new();
     let mut uncached: Vec<String> = Vec::new();
+    let mut stale: Vec<String> = Vec::new();
 
     {
         let cache_read = cache.read().await;
         for cs in &queried_callsigns {
-            match cache_read.get(cs) {
-                Some(CachedLookup::Found {
+            let Some(hit) = cache_read.lookup(cs) else {
+                uncached.push(cs.clone());
+                continue;
+            };
+            cache_hits += 1;
+            if !hit.fresh {
+                stale.push(cs.clone());
+            }
+            let result = match hit.lookup {
+                CachedLookup::Found {
                     current_call,
                     nickname,
-                }) => {
-                    cache_hits += 1;
-                    cached_results.insert(
-                        cs.clone(),
-                        LookupResult::Found(QrzInfo {
-                            current_call,
-                            nickname,
-                        }),
-                    );
-                }
-                Some(CachedLookup::NotFound) => {
-                    cache_hits += 1;
-                    cached_results.insert(cs.clone(), LookupResult::NotFound);
+                } => {
+                    if current_call.eq_ignore_ascii_case(cs)
+                        && let Some(name) = &nickname
+                    {
+                        prior_names.insert(cs.clone(), name.clone());
+                    }
+                    LookupResult::Found(QrzInfo {
+                        current_call,
+                        nickname,
+                        license_expires: cache_read.license_expires(cs),
+                    })
                 }
-                None => uncached.push(cs.clone()),
-            }
+                CachedLookup::NotFound => LookupResult::NotFound,
+            };
+            cached_results.insert(cs.clone(), result);
         }
     }
 
+    if !stale.is_empty() {
+        info!(
+            "[{}] {} stale cache entries, refreshing up to {} this cycle",
+            org_name,
+            stale.len(),
+            max_stale_refreshes
+        );
+    }
+
+    // New members first, then a bounded number of stale refreshes.
+    stale.truncate(*max_stale_refreshes);
+    uncached.extend(stale);
+    if !uncached.is_empty() && breaker.is_open() {
+        info!(
+            "[{}] QRZ circuit breaker open: skipping {} lookup(s) this cycle, using cached data",
+            org_name,
+            uncached.len()
+        );
+        uncached.clear();
+    }
     let lookups_needed = uncached.len();
+    let granted = budget.take(lookups_needed);
+    let deferred = uncached.split_off(granted);
+
     if lookups_needed > 0 {
         info!(
             "[{}] QRZ enrichment: {} cache hits, {} lookups needed (max {} concurrent)",
             org_name, cache_hits, lookups_needed, max_concurrent
         );
+        if !deferred.is_empty() {
+            info!(
+                "[{}] Lookup budget exhausted: deferring {} lookup(s) to a later cycle ({} used today)",
+                org_name,
+                deferred.len(),
+                budget.used_today()
+            );
+        }
     } else {
         info!(
             "[{}] QRZ enrichment: {} cache hits, 0 lookups needed",
@@ -354,14 +1199,19 @@ async fn enrich_with_nicknames(
     let fresh_results: Vec<(String, LookupResult)> = stream::iter(uncached)
         .map(|callsign| {
             let qrz = qrz.clone();
+            let cache = cache.clone();
             let semaphore = semaphore.clone();
             let org_name = org_name.to_string();
             async move {
                 let _permit = semaphore.acquire().await.unwrap();
+                if !breaker.allow() {
+                    return (callsign, LookupResult::Skipped);
+                }
                 sleep(Duration::from_millis(50)).await;
 
                 let result = match qrz.lookup(&callsign).await {
                     Ok(Some(info)) => {
+                        breaker.record_success();
                         debug!(
                             "[{}] QRZ {} -> current_call={} nickname={:?}",
                             org_name, callsign, info.current_call, info.nickname
@@ -369,15 +1219,47 @@ async fn enrich_with_nicknames(
                         LookupResult::Found(info)
                     }
                     Ok(None) => {
+                        breaker.record_success();
                         debug!("[{}] QRZ {} not found", org_name, callsign);
                         LookupResult::NotFound
                     }
+                    Err(e) if QrzError::is_auth(&e) => {
+                        if breaker.trip() {
+                            warn!(
+                                "[{}] {}; skipping QRZ lookups for {}s and using cached data",
+                                org_name,
+                                redact::error_chain(&e),
+                                breaker.cooldown().as_secs()
+                            );
+                        }
+                        LookupResult::Error
+                    }
                     Err(e) => {
-                        warn!("[{}] QRZ lookup failed for {}: {}", org_name, callsign, e);
+                        warn!(
+                            "[{}] QRZ lookup failed for {}: {}",
+                            org_name,
+                            callsign,
+                            redact::error_chain(&e)
+                        );
+                        if breaker.record_failure() {
+                            warn!(
+                                "[{}] QRZ circuit breaker open after repeated failures; skipping lookups for {}s and using cached data",
+                                org_name,
+                                breaker.cooldown().as_secs()
+                            );
+                        }
                         LookupResult::Error
                     }
                 };
 
+                // Persist as each lookup completes so a crash mid-cycle
+                // doesn't lose finished lookups (don't cache transient errors).
+                match &result {
+                    LookupResult::Found(info) => cache.write().await.insert_found(&callsign, info),
+                    LookupResult::NotFound => cache.write().await.insert_not_found(&callsign),
+                    LookupResult::Error | LookupResult::Skipped => {} // try again next cycle
+                }
+
                 (callsign, result)
             }
         })
@@ -385,65 +1267,141 @@ async fn enrich_with_nicknames(
         .collect()
         .await;
 
-    // Persist new lookups (don't cache transient errors).
-    {
-        let mut cache_write = cache.write().await;
-        for (queried, result) in &fresh_results {
-            match result {
-                LookupResult::Found(info) => cache_write.insert_found(queried, info),
-                LookupResult::NotFound => cache_write.insert_not_found(queried),
-                LookupResult::Error => {} // try again next cycle
-            }
-        }
-        if let Err(e) = cache_write.save() {
-            warn!("[{}] Failed to save QRZ cache: {}", org_name, e);
-        }
+    // Lookups skipped by the breaker never reached QRZ
+    let skipped = fresh_results
+        .iter()
+        .filter(|(_, r)| matches!(r, LookupResult::Skipped))
+        .count();
+    budget.refund(skipped);
+    let status = qrz.session_status().await;
+    if let (Some(count), Some(seen)) = (status.count, status.count_day) {
+        budget.observe_remote_count(count, seen);
+    }
+
+    if skipped > 0 {
+        info!(
+            "[{}] Skipped {} lookup(s) while QRZ circuit breaker was open",
+            org_name, skipped
+        );
+    }
+
+    // Flush file-backed caches (SQLite has already committed each insert).
+    if let Err(e) = cache.write().await.save() {
+        warn!(
+            "[{}] Failed to save QRZ cache: {}",
+            org_name,
+            redact::error_chain(&e)
+        );
     }
 
-    // Build a unified queried -> result map.
+    // Build a unified queried -> result map. Failed refreshes keep serving
+    // the stale cached result.
     let mut by_queried: HashMap<String, LookupResult> = cached_results;
     for (q, r) in fresh_results {
-        by_queried.insert(q, r);
+        if matches!(r, LookupResult::Found(_) | LookupResult::NotFound) {
+            by_queried.insert(q, r);
+        }
     }
 
     // Apply: replace member.callsign with QRZ's current_call, set nickname.
     let mut remapped = 0;
+    let mut rejected = 0;
     let mut nicknames_found = 0;
+    let cache = cache.read().await;
     for member in members.iter_mut() {
         match by_queried.get(&member.callsign) {
             Some(LookupResult::Found(info)) => {
-                if !info.current_call.eq_ignore_ascii_case(&member.callsign) {
+                if policy.remaps && !info.current_call.eq_ignore_ascii_case(&member.callsign) {
+                    // The QRZ record belongs to someone else: keep the
+                    // roster call and don't take that operator's name either.
+                    if policy.verify_remaps
+                        && remap_rejected(member, info, &prior_names, org_name, remap_history, now)
+                    {
+                        rejected += 1;
+                        continue;
+                    }
                     info!(
                         "[{}] Remapping {} -> {} (operator's current callsign per QRZ)",
                         org_name, member.callsign, info.current_call
                     );
+                    member.remapped_since = cache.remapped_since(&member.callsign);
                     member.callsign = info.current_call.clone();
+                    member.remap_source = Some(Source::Qrz);
                     remapped += 1;
                 }
-                if let Some(nick) = &info.nickname {
-                    member.nickname = Some(nick.clone());
+                if policy.nicknames
+                    && !(roster_nickname_wins && member.nickname.is_some())
+                    && let Some(nick) = info.nickname.as_deref().and_then(clean_name)
+                {
+                    member.nickname = Some(nick);
+                    member.nickname_source = Some(Source::Qrz);
                     nicknames_found += 1;
                 }
+                member.license = info.license_expires.map(License::from_qrz);
             }
-            Some(LookupResult::NotFound) | Some(LookupResult::Error) | None => {}
+            Some(LookupResult::NotFound)
+            | Some(LookupResult::Error)
+            | Some(LookupResult::Skipped)
+            | None => {}
         }
     }
 
-    // Dedupe: if remap produced two rows with the same callsign, keep the
-    // one with the numerically lower member id (typical convention: lower
-    // number = older membership). Stable: input order otherwise preserved.
-    let dropped = dedupe_by_callsign(members);
-    if dropped > 0 {
-        info!(
-            "[{}] Dropped {} duplicate row(s) after callsign remap",
-            org_name, dropped
+    info!(
+        "[{}] QRZ enrichment complete ({}): {} nicknames found, {} callsigns remapped, {} remaps rejected",
+        org_name, policy, nicknames_found, remapped, rejected
+    );
+}
+
+/// Identity check for a remap: the QRZ record's first name has to match
+/// the member's roster name, or failing that the name cached for the
+/// roster call. Remaps with nothing to compare are allowed. A pair rejected
+/// once stays rejected. Rejections are recorded in the remap history.
+fn remap_rejected(
+    member: &Member,
+    info: &QrzInfo,
+    prior_names: &HashMap<String, String>,
+    org_name: &str,
+    remap_history: &mut RemapHistory,
+    now: chrono::DateTime<Utc>,
+) -> bool {
+    if remap_history.recheck_rejection(org_name, &member.callsign, &info.current_call, now) {
+        debug!(
+            "[{}] Keeping {} (remap to {} was rejected before)",
+            org_name, member.callsign, info.current_call
         );
+        return true;
     }
 
-    info!(
-        "[{}] QRZ enrichment complete: {} nicknames found, {} callsigns remapped",
-        org_name, nicknames_found, remapped
+    let expected = member.roster_nickname.clone().or_else(|| {
+        prior_names
+            .get(&member.callsign)
+            .and_then(|n| clean_name(n))
+    });
+    let qrz_name = info.nickname.as_deref().and_then(clean_name);
+    let (Some(expected), Some(qrz_name)) = (expected, qrz_name) else {
+        return false;
+    };
+    if same_person(&expected, &qrz_name) {
+        return false;
+    }
+
+    warn!(
+        "[{}] Rejecting remap {} -> {}: QRZ name {:?} doesn't match member {} ({:?})",
+        org_name, member.callsign, info.current_call, qrz_name, member.member_id, expected
+    );
+    remap_history.reject(
+        org_name,
+        RejectedRemap {
+            member_id: member.member_id.clone(),
+            old_call: member.callsign.clone(),
+            new_call: info.current_call.clone(),
+            expected_name: expected,
+            qrz_name,
+            first_seen: now,
+            last_seen: now,
+        },
     );
+    true
 }
 
 /// Drop duplicate rows that share a callsign after remapping. Returns the
@@ -487,37 +1445,6 @@ fn dedupe_by_callsign(members: &mut Vec<Member>) -> usize {
     original_len - members.len()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn m(callsign: &str, member_id: &str) -> Member {
-        Member {
-            callsign: callsign.to_string(),
-            member_id: member_id.to_string(),
-            nickname: None,
-        }
-    }
-
-    #[test]
-    fn dedupe_keeps_lowest_member_id() {
-        let mut members = vec![m("W6JY", "100"), m("W6JY", "16"), m("K4MW", "1")];
-        let dropped = dedupe_by_callsign(&mut members);
-        assert_eq!(dropped, 1);
-        assert_eq!(members.len(), 2);
-        let w6jy = members.iter().find(|m| m.callsign == "W6JY").unwrap();
-        assert_eq!(w6jy.member_id, "16");
-    }
-
-    #[test]
-    fn dedupe_no_duplicates_is_noop() {
-        let mut members = vec![m("W6JY", "16"), m("K4MW", "1")];
-        let dropped = dedupe_by_callsign(&mut members);
-        assert_eq!(dropped, 0);
-        assert_eq!(members.len(), 2);
-    }
-}
-
 /// Run connectivity diagnostics to help debug network issues
 async fn run_connectivity_check() {
     // Test targets: one from each service we use
@@ -547,3 +1474,30 @@ async fn run_connectivity_check() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn m(callsign: &str, member_id: &str) -> Member {
+        Member::new(callsign.to_string(), member_id.to_string())
+    }
+
+    #[test]
+    fn dedupe_keeps_lowest_member_id() {
+        let mut members = vec![m("W6JY", "100"), m("W6JY", "16"), m("K4MW", "1")];
+        let dropped = dedupe_by_callsign(&mut members);
+        assert_eq!(dropped, 1);
+        assert_eq!(members.len(), 2);
+        let w6jy = members.iter().find(|m| m.callsign == "W6JY").unwrap();
+        assert_eq!(w6jy.member_id, "16");
+    }
+
+    #[test]
+    fn dedupe_no_duplicates_is_noop() {
+        let mut members = vec![m("W6JY", "16"), m("K4MW", "1")];
+        let dropped = dedupe_by_callsign(&mut members);
+        assert_eq!(dropped, 0);
+        assert_eq!(members.len(), 2);
+    }
+}
@@ -0,0 +1,225 @@
+//! Name cleanup shared by every nickname source.
+//!
+//! Roster name columns and QRZ `<fname>` both come in with noise: trailing
+//! middle initials ("James S"), annotations ("Gordon [SK]"), quoted or
+//! parenthesized preferred names ("Robert (Bob)"), XML entities and
+//! shouty capitals. Both sources go through [`clean_name`] so a member's
+//! note reads the same whichever one supplied it.
+//!
+//! [`same_person`] compares two such names loosely, for checking that a
+//! QRZ record belongs to the member the roster describes.
+
+/// Common given names and their short forms. Short forms that are simply
+/// a prefix of the full name ("Dan", "Greg") don't need listing.
+const NAME_GROUPS: &[&[&str]] = &[
+    &["robert", "bob", "bobby", "rob", "bert"],
+    &["william", "bill", "billy", "will", "willy"],
+    &["james", "jim", "jimmy", "jamie"],
+    &["michael", "mike", "mick"],
+    &["richard", "rick", "ricky", "dick", "rich"],
+    &["john", "jack", "johnny", "jon"],
+    &["charles", "chuck", "charlie"],
+    &["edward", "ed", "eddie", "ted", "ned"],
+    &["joseph", "joe", "joey"],
+    &["david", "dave"],
+    &["stephen", "steven", "steve"],
+    &["anthony", "tony"],
+    &["gerald", "jerry", "gerry"],
+    &["lawrence", "larry"],
+    &["henry", "hank", "harry"],
+    &["andrew", "andy", "drew"],
+    &["alexander", "alex", "al"],
+    &["albert", "al", "bert"],
+    &["margaret", "peggy", "maggie", "meg"],
+    &["elizabeth", "liz", "beth", "betty"],
+    &["katherine", "catherine", "kathy", "cathy", "kate", "katie"],
+    &["patricia", "pat", "patty", "trish"],
+    &["susan", "sue", "suzy"],
+    &["deborah", "debbie", "deb"],
+];
+
+/// Clean a raw first-name field into a nickname, or `None` if nothing
+/// usable is left.
+pub fn clean_name(raw: &str) -> Option<String> {
+    let decoded = decode_entities(raw);
+
+    // A quoted or parenthesized name is the one the operator goes by,
+    // unless the parentheses hold an annotation ("John (SK)").
+    let preferred = delimited(&decoded, '"', '"')
+        .or_else(|| delimited(&decoded, '(', ')').filter(|n| !is_annotation(n)))
+        .filter(|n| !n.trim().is_empty());
+    let name = match preferred {
+        Some(name) => name.to_string(),
+        None => strip_annotations(&decoded),
+    };
+
+    let mut words: Vec<&str> = name.split_whitespace().collect();
+    // Drop trailing middle initials ("James S", "Mary J.").
+    while words.len() > 1 && is_initial(words[words.len() - 1]) {
+        words.pop();
+    }
+    if words.is_empty() {
+        return None;
+    }
+
+    Some(
+        words
+            .iter()
+            .map(|w| fix_case(w))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Whether two cleaned first names plausibly belong to the same person:
+/// equal ignoring case, one a prefix of the other ("Chris"/"Christopher"),
+/// or known forms of the same name ("Bob"/"Robert").
+pub fn same_person(a: &str, b: &str) -> bool {
+    let first_word = |s: &str| {
+        s.split_whitespace()
+            .next()
+            .unwrap_or("")
+            .chars()
+            .filter(|c| c.is_alphabetic())
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    let (a, b) = (first_word(a), first_word(b));
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    if a == b {
+        return true;
+    }
+    let (short, long) = if a.len() <= b.len() {
+        (&a, &b)
+    } else {
+        (&b, &a)
+    };
+    if short.chars().count() >= 3 && long.starts_with(short.as_str()) {
+        return true;
+    }
+    NAME_GROUPS
+        .iter()
+        .any(|group| group.contains(&a.as_str()) && group.contains(&b.as_str()))
+}
+
+fn decode_entities(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn delimited(s: &str, open: char, close: char) -> Option<&str> {
+    let start = s.find(open)? + open.len_utf8();
+    let len = s[start..].find(close)?;
+    Some(&s[start..start + len])
+}
+
+/// Remove `[...]` and `(...)` annotations such as `[SK]`.
+fn strip_annotations(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut depth = 0usize;
+    for c in s.chars() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Whether bracketed text is a note about the member rather than a name:
+/// `SK`, `ex-W1XYZ`, a callsign, or a short all-caps tag like `XYL`.
+fn is_annotation(text: &str) -> bool {
+    let text = text.trim();
+    let lower = text.to_lowercase();
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+    lower.starts_with("ex-")
+        || lower.starts_with("ex ")
+        || (text.chars().any(|c| c.is_ascii_digit()) && letters > 0)
+        || (letters <= 3 && !text.chars().any(char::is_lowercase))
+}
+
+fn is_initial(word: &str) -> bool {
+    let letters = word.trim_end_matches('.');
+    letters.chars().count() == 1 && letters.chars().all(char::is_alphabetic)
+}
+
+/// Title-case all-caps or all-lowercase words; leave mixed case alone so
+/// names like "McKay" or "DeWitt" survive.
+fn fix_case(word: &str) -> String {
+    let has_upper = word.chars().any(char::is_uppercase);
+    let has_lower = word.chars().any(char::is_lowercase);
+    if has_upper && has_lower {
+        return word.to_string();
+    }
+
+    let mut out = String::with_capacity(word.len());
+    let mut start_of_part = true;
+    for c in word.chars() {
+        if start_of_part {
+            out.extend(c.to_uppercase());
+        } else {
+            out.extend(c.to_lowercase());
+        }
+        start_of_part = c == '-' || c == '\'';
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_name_basic() {
+        assert_eq!(clean_name("  Mike "), Some("Mike".to_string()));
+        assert_eq!(clean_name("James S"), Some("James".to_string()));
+        assert_eq!(clean_name("Mary J."), Some("Mary".to_string()));
+        assert_eq!(clean_name("Gordon [SK]"), Some("Gordon".to_string()));
+        assert_eq!(clean_name("Mary Ann"), Some("Mary Ann".to_string()));
+        assert_eq!(clean_name(""), None);
+        assert_eq!(clean_name(" [SK] "), None);
+    }
+
+    #[test]
+    fn test_clean_name_preferred_and_case() {
+        assert_eq!(clean_name("Robert (Bob)"), Some("Bob".to_string()));
+        assert_eq!(clean_name("William \"Bill\""), Some("Bill".to_string()));
+        assert_eq!(
+            clean_name("William &quot;Bill&quot;"),
+            Some("Bill".to_string())
+        );
+        assert_eq!(clean_name("JEAN-PAUL"), Some("Jean-Paul".to_string()));
+        assert_eq!(clean_name("o'neil"), Some("O'Neil".to_string()));
+        assert_eq!(clean_name("McKay"), Some("McKay".to_string()));
+    }
+
+    #[test]
+    fn test_clean_name_annotations_in_parentheses() {
+        assert_eq!(clean_name("John (SK)"), Some("John".to_string()));
+        assert_eq!(clean_name("Bob (ex-W1XYZ)"), Some("Bob".to_string()));
+        assert_eq!(clean_name("Bob (K1ABC)"), Some("Bob".to_string()));
+        assert_eq!(clean_name("Jane (XYL)"), Some("Jane".to_string()));
+        // Short all-caps text reads as a tag, so the outer name is kept
+        assert_eq!(clean_name("ROBERT (BOB)"), Some("Robert".to_string()));
+        assert_eq!(clean_name("Robert (Bobby)"), Some("Bobby".to_string()));
+    }
+
+    #[test]
+    fn test_same_person() {
+        assert!(same_person("Bob", "bob"));
+        assert!(same_person("Robert", "Bob"));
+        assert!(same_person("Chris", "Christopher"));
+        assert!(same_person("Mary Ann", "Mary"));
+        assert!(!same_person("Bob", "Alice"));
+        assert!(!same_person("Al", "Alan"));
+        assert!(!same_person("", "Bob"));
+    }
+}
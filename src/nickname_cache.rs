@@ -1,6 +1,7 @@
-//! Persistent file-based cache for QRZ lookups.
+//! Persistent cache for QRZ lookups.
 //!
-//! Stores `queried-callsign -> {current_call, nickname}` mappings with TTL.
+//! Stores `queried-callsign -> {current_call, nickname}` mappings with TTL,
+//! plus the license expiry date QRZ reports for the current call.
 //! The cache is keyed on the *queried* callsign (i.e. what came out of the
 //! roster), so retired/aliased callsigns continue to map cheaply to their
 //! current canonical form on every refresh cycle.
@@ -10,18 +11,85 @@
 //! incomplete and force a re-lookup on `get()` — that way a single daemon
 //! restart backfills `current_call` for the entire roster without throwing
 //! away nicknames already stored.
+//!
+//! Found and not-found entries have separate TTLs, and each callsign gets a
+//! fixed pseudo-random jitter added to its TTL so entries cached in the same
+//! cycle don't all expire together. Expired entries aren't dropped: they're
+//! served as stale (see [`NicknameCache::lookup`]) until they're refreshed,
+//! and only pruned once they're older than the TTL plus `max_stale`.
+//!
+//! Two storage backends are supported. The JSON file backend keeps
+//! everything in memory and rewrites the file (atomically, via a temp file
+//! and rename) on [`NicknameCache::save`]. The SQLite backend commits every
+//! insert as it happens, so a crash mid-enrichment loses at most the lookup
+//! in flight. Reads always come from the in-memory copy.
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tracing::{debug, info, warn};
 
+use crate::atomic_file::write_atomic;
+use crate::hash::fnv1a;
 use crate::qrz::QrzInfo;
 
-/// Default cache TTL: 30 days
-const DEFAULT_TTL_DAYS: i64 = 30;
+/// Default TTL for found entries: 30 days
+const DEFAULT_FOUND_TTL_DAYS: i64 = 30;
+
+/// Default TTL for not-found entries: 30 days
+const DEFAULT_NOT_FOUND_TTL_DAYS: i64 = 30;
+
+/// Default maximum jitter added to each entry's TTL: 7 days
+const DEFAULT_JITTER_DAYS: i64 = 7;
+
+/// Default time past expiry that stale entries are kept: 180 days
+const DEFAULT_MAX_STALE_DAYS: i64 = 180;
+
+/// Expiry policy for cache entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheTtl {
+    /// TTL for callsigns QRZ returned a record for.
+    pub found: Duration,
+    /// TTL for callsigns QRZ reported as not found.
+    pub not_found: Duration,
+    /// Upper bound of the per-callsign jitter added to either TTL.
+    pub jitter: Duration,
+    /// How long past expiry a stale entry is kept before being pruned.
+    pub max_stale: Duration,
+}
+
+impl Default for CacheTtl {
+    fn default() -> Self {
+        Self {
+            found: Duration::days(DEFAULT_FOUND_TTL_DAYS),
+            not_found: Duration::days(DEFAULT_NOT_FOUND_TTL_DAYS),
+            jitter: Duration::days(DEFAULT_JITTER_DAYS),
+            max_stale: Duration::days(DEFAULT_MAX_STALE_DAYS),
+        }
+    }
+}
+
+impl CacheTtl {
+    /// TTL for an entry, including the callsign's jitter. The jitter is
+    /// derived from a hash of the callsign so it's stable across restarts.
+    fn for_entry(&self, callsign: &str, entry: &CacheEntry) -> Duration {
+        let base = if entry.current_call.is_none() && entry.nickname.is_none() {
+            self.not_found
+        } else {
+            self.found
+        };
+        let jitter_secs = self.jitter.num_seconds();
+        if jitter_secs <= 0 {
+            return base;
+        }
+        let hash = fnv1a(callsign.as_bytes());
+        base + Duration::seconds((hash % jitter_secs as u64) as i64)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CacheEntry {
@@ -33,6 +101,13 @@ struct CacheEntry {
     #[serde(default)]
     current_call: Option<String>,
     cached_at: DateTime<Utc>,
+    /// When the queried callsign was first seen remapping to `current_call`.
+    /// Carried over on refresh as long as the mapping doesn't change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    remapped_since: Option<DateTime<Utc>>,
+    /// License expiry of `current_call` from QRZ `<expdate>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    license_expires: Option<NaiveDate>,
 }
 
 impl CacheEntry {
@@ -41,22 +116,26 @@ impl CacheEntry {
             nickname: info.and_then(|i| i.nickname.clone()),
             current_call: info.map(|i| i.current_call.clone()),
             cached_at: Utc::now(),
+            remapped_since: None,
+            license_expires: info.and_then(|i| i.license_expires),
         }
     }
 
     fn from_negative() -> Self {
-        // Negative cache: callsign-not-found. We still record `current_call`
-        // as a sentinel (the queried callsign itself, uppercased) so the
-        // entry isn't mistaken for a legacy "needs backfill" row.
+        // Negative cache: callsign-not-found. Both `current_call` and
+        // `nickname` are empty; a legacy "needs backfill" row is told apart
+        // by having a nickname without a `current_call`.
         Self {
             nickname: None,
             current_call: None,
             cached_at: Utc::now(),
+            remapped_since: None,
+            license_expires: None,
         }
     }
 
-    fn is_expired(&self, ttl: Duration) -> bool {
-        Utc::now() - self.cached_at > ttl
+    fn age(&self) -> Duration {
+        Utc::now() - self.cached_at
     }
 }
 
@@ -65,11 +144,52 @@ struct CacheData {
     entries: HashMap<String, CacheEntry>,
 }
 
-/// Persistent QRZ-lookup cache with file-based storage.
+impl CacheData {
+    fn from_json_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read cache file: {}", path.display()))?;
+
+        // Handle empty files
+        if content.trim().is_empty() {
+            debug!("Cache file is empty, starting fresh");
+            return Ok(Self::default());
+        }
+
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse cache file: {}", path.display()))
+    }
+
+    /// Drop entries that have been stale for too long and return their
+    /// callsigns. Merely expired entries are kept and served until refreshed.
+    fn prune_long_expired(&mut self, ttl: &CacheTtl) -> Vec<String> {
+        let pruned: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(call, entry)| entry.age() > ttl.for_entry(call, entry) + ttl.max_stale)
+            .map(|(call, _)| call.clone())
+            .collect();
+        for call in &pruned {
+            self.entries.remove(call);
+        }
+        pruned
+    }
+}
+
+/// Where the cache is persisted.
+enum Backend {
+    /// Whole cache serialized to a JSON file on `save()`.
+    Json(PathBuf),
+    /// One row per callsign, written on every insert.
+    Sqlite(Mutex<Connection>),
+    /// Not persisted (fallback when the configured store can't be opened).
+    Memory,
+}
+
+/// Persistent QRZ-lookup cache with JSON-file or SQLite storage.
 pub struct NicknameCache {
-    path: PathBuf,
+    backend: Backend,
     data: CacheData,
-    ttl: Duration,
+    ttl: CacheTtl,
     dirty: bool,
 }
 
@@ -85,91 +205,426 @@ pub enum CachedLookup {
     NotFound,
 }
 
+/// One cache entry as shown by the cache management commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheRecord {
+    /// Queried (roster) callsign the entry is keyed on.
+    pub callsign: String,
+    pub hit: CacheHit,
+    /// Row written before `current_call` existed; refreshed on next lookup.
+    pub legacy: bool,
+    pub cached_at: DateTime<Utc>,
+    /// When the entry's TTL (including jitter) runs out.
+    pub expires_at: DateTime<Utc>,
+    pub remapped_since: Option<DateTime<Utc>>,
+}
+
+/// Flat row used for CSV export/import.
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRow {
+    callsign: String,
+    nickname: Option<String>,
+    current_call: Option<String>,
+    cached_at: DateTime<Utc>,
+    remapped_since: Option<DateTime<Utc>>,
+    /// Missing from exports made before license tracking.
+    #[serde(default)]
+    license_expires: Option<NaiveDate>,
+}
+
+/// A cache entry together with whether it's still within its TTL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheHit {
+    pub lookup: CachedLookup,
+    /// `false` for expired entries and legacy rows. Stale hits are still
+    /// usable, but the caller should refresh them when it can.
+    pub fresh: bool,
+}
+
 impl NicknameCache {
     /// Load cache from file, or create empty if not exists.
     pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_with_ttl(path, CacheTtl::default())
+    }
+
+    /// Load cache from file with a custom expiry policy.
+    pub fn load_with_ttl(path: impl AsRef<Path>, ttl: CacheTtl) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
-        let ttl = Duration::days(DEFAULT_TTL_DAYS);
 
         let data = if path.exists() {
-            let content = std::fs::read_to_string(&path)
-                .with_context(|| format!("Failed to read cache file: {}", path.display()))?;
-
-            // Handle empty files
-            if content.trim().is_empty() {
-                debug!("Cache file is empty, starting fresh");
-                CacheData::default()
-            } else {
-                let mut data: CacheData = serde_json::from_str(&content)
-                    .with_context(|| format!("Failed to parse cache file: {}", path.display()))?;
-
-                // Prune expired entries on load
-                let before_count = data.entries.len();
-                data.entries.retain(|_, entry| !entry.is_expired(ttl));
-                let pruned = before_count - data.entries.len();
-
-                if pruned > 0 {
-                    info!(
-                        "Loaded QRZ lookup cache with {} entries ({} expired, pruned)",
-                        data.entries.len(),
-                        pruned
-                    );
-                } else {
-                    info!("Loaded QRZ lookup cache with {} entries", data.entries.len());
-                }
-
-                data
-            }
+            let mut data = CacheData::from_json_file(&path)?;
+            let pruned = data.prune_long_expired(&ttl);
+            log_loaded(data.entries.len(), pruned.len());
+            data
         } else {
             debug!("No existing cache file, starting fresh");
             CacheData::default()
         };
 
         Ok(Self {
-            path,
+            backend: Backend::Json(path),
             data,
             ttl,
             dirty: false,
         })
     }
 
+    /// An empty cache that is never written to disk.
+    pub fn in_memory(ttl: CacheTtl) -> Self {
+        Self {
+            backend: Backend::Memory,
+            data: CacheData::default(),
+            ttl,
+            dirty: false,
+        }
+    }
+
+    /// Open (or create) a SQLite-backed cache. Every insert is committed
+    /// immediately; `save()` only has work to do after a failed write.
+    pub fn open_sqlite(path: impl AsRef<Path>, ttl: CacheTtl) -> Result<Self> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create cache directory: {}", parent.display())
+            })?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open cache database: {}", path.display()))?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS lookups (
+                 callsign     TEXT PRIMARY KEY,
+                 nickname     TEXT,
+                 current_call TEXT,
+                 cached_at    TEXT NOT NULL
+             );",
+        )
+        .context("Failed to initialize cache database")?;
+
+        // Databases created before remap or license tracking lack these
+        // columns.
+        for column in ["remapped_since", "license_expires"] {
+            let exists = conn
+                .prepare("SELECT 1 FROM pragma_table_info('lookups') WHERE name = ?1")?
+                .exists(params![column])?;
+            if !exists {
+                conn.execute(
+                    &format!("ALTER TABLE lookups ADD COLUMN {} TEXT", column),
+                    [],
+                )
+                .context("Failed to migrate cache database")?;
+            }
+        }
+
+        let mut data = CacheData::default();
+        {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT callsign, nickname, current_call, cached_at, remapped_since,
+                            license_expires
+                     FROM lookups",
+                )
+                .context("Failed to read cache database")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            })?;
+            for row in rows {
+                let (callsign, nickname, current_call, cached_at, remapped_since, license_expires) =
+                    row?;
+                let Ok(cached_at) = DateTime::parse_from_rfc3339(&cached_at) else {
+                    warn!("Ignoring cache row for {} with bad timestamp", callsign);
+                    continue;
+                };
+                let remapped_since = remapped_since
+                    .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                    .map(|t| t.with_timezone(&Utc));
+                data.entries.insert(
+                    callsign,
+                    CacheEntry {
+                        nickname,
+                        current_call,
+                        cached_at: cached_at.with_timezone(&Utc),
+                        remapped_since,
+                        license_expires: license_expires
+                            .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
+                    },
+                );
+            }
+        }
+
+        let pruned = data.prune_long_expired(&ttl);
+        for call in &pruned {
+            conn.execute("DELETE FROM lookups WHERE callsign = ?1", params![call])?;
+        }
+        log_loaded(data.entries.len(), pruned.len());
+
+        Ok(Self {
+            backend: Backend::Sqlite(Mutex::new(conn)),
+            data,
+            ttl,
+            dirty: false,
+        })
+    }
+
+    /// Import every entry from a JSON cache file, keeping original
+    /// timestamps (and legacy rows, so they still get backfilled). Existing
+    /// entries for the same callsign are replaced. Returns the number of
+    /// entries imported.
+    pub fn import_json(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        let imported = CacheData::from_json_file(path.as_ref())?;
+        self.put_all(
+            imported
+                .entries
+                .into_iter()
+                .map(|(callsign, entry)| (callsign.to_uppercase(), entry))
+                .collect(),
+        )
+    }
+
+    /// Import entries from a JSON or CSV file, picked by extension (`.csv`
+    /// for CSV, anything else is read as a JSON cache file). Returns the
+    /// number of entries imported.
+    pub fn import(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        if !is_csv(path) {
+            return self.import_json(path);
+        }
+
+        let mut reader = csv::Reader::from_path(path)
+            .with_context(|| format!("Failed to read cache export: {}", path.display()))?;
+        let mut entries = Vec::new();
+        for row in reader.deserialize() {
+            let row: CsvRow =
+                row.with_context(|| format!("Failed to parse cache export: {}", path.display()))?;
+            entries.push((
+                row.callsign.to_uppercase(),
+                CacheEntry {
+                    nickname: row.nickname,
+                    current_call: row.current_call,
+                    cached_at: row.cached_at,
+                    remapped_since: row.remapped_since,
+                    license_expires: row.license_expires,
+                },
+            ));
+        }
+        self.put_all(entries)
+    }
+
+    /// Write every entry to a JSON or CSV file, picked by extension like
+    /// [`import`](Self::import). The JSON format is the cache file format.
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        if !is_csv(path) {
+            write_json_atomic(path, &self.data)?;
+            return Ok(self.data.entries.len());
+        }
+
+        let mut writer = csv::Writer::from_path(path)
+            .with_context(|| format!("Failed to create export file: {}", path.display()))?;
+        let mut callsigns: Vec<&String> = self.data.entries.keys().collect();
+        callsigns.sort();
+        for callsign in &callsigns {
+            let entry = &self.data.entries[*callsign];
+            writer.serialize(CsvRow {
+                callsign: callsign.to_string(),
+                nickname: entry.nickname.clone(),
+                current_call: entry.current_call.clone(),
+                cached_at: entry.cached_at,
+                remapped_since: entry.remapped_since,
+                license_expires: entry.license_expires,
+            })?;
+        }
+        writer.flush()?;
+        Ok(callsigns.len())
+    }
+
+    /// Every entry, sorted by callsign.
+    pub fn records(&self) -> Vec<CacheRecord> {
+        let mut callsigns: Vec<&String> = self.data.entries.keys().collect();
+        callsigns.sort();
+        callsigns
+            .into_iter()
+            .filter_map(|call| self.record(call))
+            .collect()
+    }
+
+    /// The entry for one callsign, including TTL details.
+    pub fn record(&self, callsign: &str) -> Option<CacheRecord> {
+        let callsign = callsign.to_uppercase();
+        let entry = self.data.entries.get(&callsign)?;
+        Some(CacheRecord {
+            hit: self.lookup(&callsign)?,
+            legacy: entry.current_call.is_none() && entry.nickname.is_some(),
+            cached_at: entry.cached_at,
+            expires_at: entry.cached_at + self.ttl.for_entry(&callsign, entry),
+            remapped_since: entry.remapped_since,
+            callsign,
+        })
+    }
+
+    /// Drop an entry so the next cycle looks the callsign up again. Returns
+    /// `false` if it wasn't cached.
+    pub fn remove(&mut self, callsign: &str) -> Result<bool> {
+        let callsign = callsign.to_uppercase();
+        if self.data.entries.remove(&callsign).is_none() {
+            return Ok(false);
+        }
+        match &self.backend {
+            Backend::Json(_) => self.dirty = true,
+            Backend::Memory => {}
+            Backend::Sqlite(conn) => {
+                conn.lock()
+                    .unwrap()
+                    .execute("DELETE FROM lookups WHERE callsign = ?1", params![callsign])
+                    .with_context(|| format!("Failed to delete cache entry for {}", callsign))?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Drop every entry past its TTL, rather than waiting for `max_stale`.
+    /// Returns the callsigns removed.
+    pub fn prune_expired(&mut self) -> Result<Vec<String>> {
+        let mut expired: Vec<String> = self
+            .data
+            .entries
+            .iter()
+            .filter(|(call, entry)| entry.age() > self.ttl.for_entry(call, entry))
+            .map(|(call, _)| call.clone())
+            .collect();
+        expired.sort();
+        for call in &expired {
+            self.remove(call)?;
+        }
+        Ok(expired)
+    }
+
     /// Look up a previously-cached callsign.
     ///
     /// Returns `None` when the entry is missing, expired, or is a legacy
     /// row written before `current_call` existed (forcing a re-lookup so
     /// the new field gets populated).
     pub fn get(&self, callsign: &str) -> Option<CachedLookup> {
+        self.lookup(callsign)
+            .filter(|hit| hit.fresh)
+            .map(|hit| hit.lookup)
+    }
+
+    /// Look up a callsign including expired entries (stale-while-revalidate).
+    ///
+    /// Legacy rows come back as a stale `Found` with the queried callsign as
+    /// `current_call`, so their nickname keeps being served while the
+    /// refresh backfills the real current callsign.
+    pub fn lookup(&self, callsign: &str) -> Option<CacheHit> {
         let callsign = callsign.to_uppercase();
         let entry = self.data.entries.get(&callsign)?;
-        if entry.is_expired(self.ttl) {
-            return None;
-        }
-        match (&entry.current_call, &entry.nickname) {
-            (Some(cc), nick) => Some(CachedLookup::Found {
-                current_call: cc.clone(),
-                nickname: nick.clone(),
-            }),
-            (None, None) => Some(CachedLookup::NotFound),
-            // Legacy row: nickname populated, current_call missing. Force a
-            // re-lookup so we can backfill the canonical callsign.
-            (None, Some(_)) => None,
-        }
+        let expired = entry.age() > self.ttl.for_entry(&callsign, entry);
+        let (lookup, fresh) = match (&entry.current_call, &entry.nickname) {
+            (Some(cc), nick) => (
+                CachedLookup::Found {
+                    current_call: cc.clone(),
+                    nickname: nick.clone(),
+                },
+                !expired,
+            ),
+            (None, None) => (CachedLookup::NotFound, !expired),
+            // Legacy row: nickname populated, current_call missing. Always
+            // stale so we re-lookup and backfill the canonical callsign.
+            (None, Some(nick)) => (
+                CachedLookup::Found {
+                    current_call: callsign.clone(),
+                    nickname: Some(nick.clone()),
+                },
+                false,
+            ),
+        };
+        Some(CacheHit { lookup, fresh })
     }
 
     /// Insert a positive QRZ result.
     pub fn insert_found(&mut self, queried: &str, info: &QrzInfo) {
         let queried = queried.to_uppercase();
+        let mut entry = CacheEntry::from_info(Some(info));
+        if !info.current_call.eq_ignore_ascii_case(&queried) {
+            // Keep the original first-seen time while the mapping holds.
+            entry.remapped_since = self
+                .data
+                .entries
+                .get(&queried)
+                .filter(|prev| prev.current_call.as_deref() == Some(info.current_call.as_str()))
+                .and_then(|prev| prev.remapped_since)
+                .or(Some(entry.cached_at));
+        }
+        self.put(queried, entry);
+    }
+
+    /// When `queried` was first seen remapping to its current callsign, or
+    /// `None` if it doesn't remap (or the cache predates remap tracking).
+    pub fn remapped_since(&self, queried: &str) -> Option<DateTime<Utc>> {
         self.data
             .entries
-            .insert(queried, CacheEntry::from_info(Some(info)));
-        self.dirty = true;
+            .get(&queried.to_uppercase())
+            .and_then(|e| e.remapped_since)
+    }
+
+    /// License expiry QRZ reported for `queried`'s current callsign.
+    pub fn license_expires(&self, queried: &str) -> Option<NaiveDate> {
+        self.data
+            .entries
+            .get(&queried.to_uppercase())
+            .and_then(|e| e.license_expires)
     }
 
     /// Insert a negative result (callsign not found).
     pub fn insert_not_found(&mut self, queried: &str) {
-        let queried = queried.to_uppercase();
-        self.data.entries.insert(queried, CacheEntry::from_negative());
-        self.dirty = true;
+        self.put(queried.to_uppercase(), CacheEntry::from_negative());
+    }
+
+    /// Store an entry in memory and, for SQLite, commit it right away. A
+    /// failed SQLite write marks the cache dirty so `save()` retries it.
+    fn put(&mut self, callsign: String, entry: CacheEntry) {
+        match &self.backend {
+            Backend::Json(_) => self.dirty = true,
+            Backend::Memory => {}
+            Backend::Sqlite(conn) => {
+                if let Err(e) = write_row(&conn.lock().unwrap(), &callsign, &entry) {
+                    warn!("Failed to write cache entry for {}: {}", callsign, e);
+                    self.dirty = true;
+                }
+            }
+        }
+        self.data.entries.insert(callsign, entry);
+    }
+
+    /// Store many entries at once, in a single transaction on SQLite so a
+    /// large import is one commit and all-or-nothing. Returns the count.
+    fn put_all(&mut self, entries: Vec<(String, CacheEntry)>) -> Result<usize> {
+        match &self.backend {
+            Backend::Json(_) => self.dirty = true,
+            Backend::Memory => {}
+            Backend::Sqlite(conn) => {
+                let mut conn = conn.lock().unwrap();
+                let tx = conn.transaction()?;
+                for (callsign, entry) in &entries {
+                    write_row(&tx, callsign, entry)?;
+                }
+                tx.commit().context("Failed to commit cache import")?;
+            }
+        }
+        let count = entries.len();
+        self.data.entries.extend(entries);
+        Ok(count)
     }
 
     /// Save cache to disk if dirty.
@@ -178,20 +633,26 @@ impl NicknameCache {
             return Ok(());
         }
 
-        if let Some(parent) = self.path.parent() {
-            std::fs::create_dir_all(parent).with_context(|| {
-                format!("Failed to create cache directory: {}", parent.display())
-            })?;
+        match &self.backend {
+            Backend::Json(path) => write_json_atomic(path, &self.data)?,
+            Backend::Sqlite(conn) => {
+                // Only reached after a failed incremental write: rewrite
+                // everything in one transaction.
+                let mut conn = conn.lock().unwrap();
+                let tx = conn.transaction()?;
+                for (callsign, entry) in &self.data.entries {
+                    write_row(&tx, callsign, entry)?;
+                }
+                tx.commit().context("Failed to commit cache database")?;
+            }
+            Backend::Memory => {}
         }
 
-        let content =
-            serde_json::to_string_pretty(&self.data).context("Failed to serialize cache")?;
-
-        std::fs::write(&self.path, content)
-            .with_context(|| format!("Failed to write cache file: {}", self.path.display()))?;
-
         self.dirty = false;
-        debug!("Saved QRZ lookup cache ({} entries)", self.data.entries.len());
+        debug!(
+            "Saved QRZ lookup cache ({} entries)",
+            self.data.entries.len()
+        );
 
         Ok(())
     }
@@ -205,6 +666,46 @@ impl NicknameCache {
     }
 }
 
+fn log_loaded(entries: usize, pruned: usize) {
+    if pruned > 0 {
+        info!(
+            "Loaded QRZ lookup cache with {} entries ({} long expired, pruned)",
+            entries, pruned
+        );
+    } else {
+        info!("Loaded QRZ lookup cache with {} entries", entries);
+    }
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+fn write_row(conn: &Connection, callsign: &str, entry: &CacheEntry) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO lookups
+             (callsign, nickname, current_call, cached_at, remapped_since, license_expires)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            callsign,
+            entry.nickname,
+            entry.current_call,
+            entry.cached_at.to_rfc3339(),
+            entry.remapped_since.map(|t| t.to_rfc3339()),
+            entry.license_expires.map(|d| d.to_string())
+        ],
+    )?;
+    Ok(())
+}
+
+/// Write the JSON cache via a temp file and rename, so a crash mid-write
+/// never leaves a truncated cache behind.
+fn write_json_atomic(path: &Path, data: &CacheData) -> Result<()> {
+    let content = serde_json::to_string_pretty(data).context("Failed to serialize cache")?;
+    write_atomic(path, content.as_bytes()).context("Failed to write cache file")
+}
+
 impl Drop for NicknameCache {
     fn drop(&mut self) {
         if self.dirty
@@ -224,6 +725,7 @@ mod tests {
         QrzInfo {
             current_call: current.to_string(),
             nickname: nick.map(|s| s.to_string()),
+            license_expires: None,
         }
     }
 
@@ -290,20 +792,319 @@ mod tests {
         );
     }
 
+    fn write_cache(path: &Path, entries: &[(&str, &str)]) {
+        let entries: Vec<String> = entries
+            .iter()
+            .map(|(call, body)| format!("\"{}\": {}", call, body))
+            .collect();
+        std::fs::write(path, format!("{{\"entries\": {{{}}}}}", entries.join(","))).unwrap();
+    }
+
+    fn days_ago(days: i64) -> String {
+        (Utc::now() - Duration::days(days)).to_rfc3339()
+    }
+
+    #[test]
+    fn test_separate_ttls_for_found_and_not_found() {
+        let temp = NamedTempFile::new().unwrap();
+        let ten_days_ago = days_ago(10);
+        write_cache(
+            temp.path(),
+            &[
+                (
+                    "K4MW",
+                    &format!(
+                        r#"{{"nickname": "Mike", "current_call": "K4MW", "cached_at": "{}"}}"#,
+                        ten_days_ago
+                    ),
+                ),
+                (
+                    "ZZ9ZZZ",
+                    &format!(r#"{{"nickname": null, "cached_at": "{}"}}"#, ten_days_ago),
+                ),
+            ],
+        );
+
+        let ttl = CacheTtl {
+            found: Duration::days(30),
+            not_found: Duration::days(7),
+            jitter: Duration::zero(),
+            max_stale: Duration::days(180),
+        };
+        let cache = NicknameCache::load_with_ttl(temp.path(), ttl).unwrap();
+
+        assert!(cache.get("K4MW").is_some());
+        assert_eq!(cache.get("ZZ9ZZZ"), None);
+        assert_eq!(
+            cache.lookup("ZZ9ZZZ"),
+            Some(CacheHit {
+                lookup: CachedLookup::NotFound,
+                fresh: false
+            })
+        );
+    }
+
+    #[test]
+    fn test_jitter_is_bounded_and_stable() {
+        let ttl = CacheTtl {
+            jitter: Duration::days(7),
+            ..CacheTtl::default()
+        };
+        let entry = CacheEntry::from_info(Some(&info("K4MW", None)));
+        let a = ttl.for_entry("K4MW", &entry);
+        assert_eq!(a, ttl.for_entry("K4MW", &entry));
+        assert!(a >= ttl.found && a < ttl.found + ttl.jitter);
+        // Pinned, so a toolchain upgrade can't reshuffle every expiry
+        assert_eq!(
+            a - ttl.found,
+            Duration::seconds((fnv1a(b"K4MW") % 604_800) as i64)
+        );
+
+        let spread: std::collections::HashSet<_> = ["K4MW", "W6JY", "WN7JT", "KI7QCF", "VK1AO"]
+            .iter()
+            .map(|call| ttl.for_entry(call, &entry))
+            .collect();
+        assert!(spread.len() > 1);
+    }
+
+    #[test]
+    fn test_expired_entries_served_stale_until_pruned() {
+        let temp = NamedTempFile::new().unwrap();
+        write_cache(
+            temp.path(),
+            &[
+                (
+                    "W6JSV",
+                    &format!(
+                        r#"{{"nickname": "Jay", "current_call": "W6JY", "cached_at": "{}"}}"#,
+                        days_ago(60)
+                    ),
+                ),
+                (
+                    "N0OLD",
+                    &format!(
+                        r#"{{"nickname": "Ed", "current_call": "N0OLD", "cached_at": "{}"}}"#,
+                        days_ago(400)
+                    ),
+                ),
+            ],
+        );
+
+        let cache = NicknameCache::load(temp.path()).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get("W6JSV"), None);
+        assert_eq!(
+            cache.lookup("W6JSV"),
+            Some(CacheHit {
+                lookup: CachedLookup::Found {
+                    current_call: "W6JY".to_string(),
+                    nickname: Some("Jay".to_string())
+                },
+                fresh: false
+            })
+        );
+        assert_eq!(cache.lookup("N0OLD"), None);
+    }
+
+    #[test]
+    fn test_sqlite_commits_each_insert() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.db");
+
+        let mut cache = NicknameCache::open_sqlite(&path, CacheTtl::default()).unwrap();
+        cache.insert_found("W6JSV", &info("W6JY", Some("Jay")));
+        cache.insert_not_found("ZZ9ZZZ");
+        // Simulate a crash: no save(), no Drop.
+        std::mem::forget(cache);
+
+        let cache = NicknameCache::open_sqlite(&path, CacheTtl::default()).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(
+            cache.get("W6JSV"),
+            Some(CachedLookup::Found {
+                current_call: "W6JY".to_string(),
+                nickname: Some("Jay".to_string())
+            })
+        );
+        assert_eq!(cache.get("ZZ9ZZZ"), Some(CachedLookup::NotFound));
+    }
+
+    #[test]
+    fn test_sqlite_imports_json_including_legacy_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("nickname_cache.json");
+        write_cache(
+            &json_path,
+            &[
+                (
+                    "K4MW",
+                    &format!(
+                        r#"{{"nickname": "Mike", "current_call": "K4MW", "cached_at": "{}"}}"#,
+                        days_ago(1)
+                    ),
+                ),
+                (
+                    "W6JSV",
+                    &format!(r#"{{"nickname": "Jay", "cached_at": "{}"}}"#, days_ago(1)),
+                ),
+            ],
+        );
+
+        let db_path = dir.path().join("cache.db");
+        {
+            let mut cache = NicknameCache::open_sqlite(&db_path, CacheTtl::default()).unwrap();
+            assert_eq!(cache.import_json(&json_path).unwrap(), 2);
+        }
+
+        let cache = NicknameCache::open_sqlite(&db_path, CacheTtl::default()).unwrap();
+        assert!(cache.get("K4MW").is_some());
+        // Legacy row survives the import and still forces a backfill.
+        assert_eq!(cache.get("W6JSV"), None);
+        assert!(!cache.lookup("W6JSV").unwrap().fresh);
+    }
+
+    #[test]
+    fn test_remapped_since_survives_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.db");
+
+        let first_seen = {
+            let mut cache = NicknameCache::open_sqlite(&path, CacheTtl::default()).unwrap();
+            cache.insert_found("K4MW", &info("K4MW", Some("Mike")));
+            assert_eq!(cache.remapped_since("K4MW"), None);
+
+            cache.insert_found("W6JSV", &info("W6JY", Some("Jay")));
+            cache.remapped_since("W6JSV").unwrap()
+        };
+
+        let mut cache = NicknameCache::open_sqlite(&path, CacheTtl::default()).unwrap();
+        assert_eq!(cache.remapped_since("w6jsv"), Some(first_seen));
+
+        // Same mapping on refresh keeps the first-seen time.
+        cache.insert_found("W6JSV", &info("W6JY", Some("Jay")));
+        assert_eq!(cache.remapped_since("W6JSV"), Some(first_seen));
+
+        // A different mapping starts over.
+        cache.insert_found("W6JSV", &info("K6NEW", Some("Jay")));
+        assert!(cache.remapped_since("W6JSV").unwrap() > first_seen);
+    }
+
+    #[test]
+    fn test_remove_and_prune_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("nickname_cache.json");
+        write_cache(
+            &json_path,
+            &[
+                (
+                    "K4MW",
+                    &format!(
+                        r#"{{"nickname": "Mike", "current_call": "K4MW", "cached_at": "{}"}}"#,
+                        days_ago(1)
+                    ),
+                ),
+                (
+                    "W6JSV",
+                    &format!(
+                        r#"{{"nickname": "Jay", "current_call": "W6JY", "cached_at": "{}"}}"#,
+                        days_ago(60)
+                    ),
+                ),
+                (
+                    "ZZ9ZZZ",
+                    &format!(r#"{{"nickname": null, "cached_at": "{}"}}"#, days_ago(1)),
+                ),
+            ],
+        );
+
+        let db_path = dir.path().join("cache.db");
+        {
+            let mut cache = NicknameCache::open_sqlite(&db_path, CacheTtl::default()).unwrap();
+            cache.import_json(&json_path).unwrap();
+            assert!(cache.remove("zz9zzz").unwrap());
+            assert!(!cache.remove("ZZ9ZZZ").unwrap());
+            assert_eq!(cache.prune_expired().unwrap(), vec!["W6JSV".to_string()]);
+        }
+
+        let cache = NicknameCache::open_sqlite(&db_path, CacheTtl::default()).unwrap();
+        let records = cache.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].callsign, "K4MW");
+        assert!(records[0].hit.fresh);
+        assert!(records[0].expires_at > Utc::now());
+    }
+
+    #[test]
+    fn test_csv_export_import_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv_path = dir.path().join("export.csv");
+
+        let mut cache = NicknameCache::in_memory(CacheTtl::default());
+        cache.insert_found("W6JSV", &info("W6JY", Some("Jay")));
+        cache.insert_not_found("ZZ9ZZZ");
+        assert_eq!(cache.export(&csv_path).unwrap(), 2);
+
+        let mut imported = NicknameCache::in_memory(CacheTtl::default());
+        assert_eq!(imported.import(&csv_path).unwrap(), 2);
+        assert_eq!(imported.records(), cache.records());
+        assert_eq!(imported.get("ZZ9ZZZ"), Some(CachedLookup::NotFound));
+    }
+
+    #[test]
+    fn test_license_expires_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cache.db");
+        let csv_path = dir.path().join("export.csv");
+        let expires = NaiveDate::from_ymd_opt(2031, 4, 12);
+
+        let mut cache = NicknameCache::open_sqlite(&db_path, CacheTtl::default()).unwrap();
+        cache.insert_found(
+            "K4MW",
+            &QrzInfo {
+                license_expires: expires,
+                ..info("K4MW", Some("Mike"))
+            },
+        );
+        cache.insert_found("W6JSV", &info("W6JY", Some("Jay")));
+        drop(cache);
+
+        let cache = NicknameCache::open_sqlite(&db_path, CacheTtl::default()).unwrap();
+        assert_eq!(cache.license_expires("k4mw"), expires);
+        assert_eq!(cache.license_expires("W6JSV"), None);
+
+        cache.export(&csv_path).unwrap();
+        let mut imported = NicknameCache::in_memory(CacheTtl::default());
+        imported.import(&csv_path).unwrap();
+        assert_eq!(imported.license_expires("K4MW"), expires);
+    }
+
     #[test]
     fn test_legacy_entry_forces_relookup() {
         // Hand-write a legacy cache file (no current_call field, nickname set).
+        // Recent enough that only the missing field makes it incomplete.
         let temp = NamedTempFile::new().unwrap();
         let path = temp.path().to_path_buf();
-        let legacy = r#"{
-            "entries": {
-                "W6JSV": { "nickname": "Jay", "cached_at": "2026-04-01T00:00:00Z" }
-            }
-        }"#;
-        std::fs::write(&path, legacy).unwrap();
+        write_cache(
+            &path,
+            &[(
+                "W6JSV",
+                &format!(r#"{{ "nickname": "Jay", "cached_at": "{}" }}"#, days_ago(1)),
+            )],
+        );
 
         let cache = NicknameCache::load(&path).unwrap();
         // Returns None so the caller will hit QRZ and backfill current_call.
         assert_eq!(cache.get("W6JSV"), None);
+        // The nickname is still served stale in the meantime.
+        assert_eq!(
+            cache.lookup("W6JSV"),
+            Some(CacheHit {
+                lookup: CachedLookup::Found {
+                    current_call: "W6JSV".to_string(),
+                    nickname: Some("Jay".to_string())
+                },
+                fresh: false
+            })
+        );
     }
 }
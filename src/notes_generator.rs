@@ -1,10 +1,105 @@
+use crate::config::HeaderConfig;
 use crate::csv_fetcher::Member;
-use chrono::Utc;
+use crate::sort::{SortKey, SortOrder};
+use crate::template::{LineTemplate, Placeholder, expand_header, member_value};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+
+/// Settings for emitting lines under members' former callsigns.
+struct FormerCalls {
+    marker: String,
+    max_age: Option<Duration>,
+}
+
+/// Header content beyond the title line.
+#[derive(Debug, Clone)]
+pub struct Header {
+    pub description: Option<String>,
+    /// Extra comment lines after the description
+    pub lines: Vec<String>,
+    pub timestamp: bool,
+    pub do_not_edit: bool,
+    /// Values for header placeholders the generator doesn't fill in itself
+    /// (it knows `label`, `member_count` and `generated`).
+    pub metadata: Vec<(String, String)>,
+}
+
+impl Header {
+    /// The header a `[header]` config table describes, with `metadata` for
+    /// its placeholders.
+    pub fn from_config(config: &HeaderConfig, metadata: Vec<(String, String)>) -> Self {
+        Self {
+            description: config.description.clone(),
+            lines: config.lines.clone(),
+            timestamp: config.timestamp,
+            do_not_edit: config.do_not_edit,
+            metadata,
+        }
+    }
+
+    /// The comment block at the top of a notes file, ending in a blank line.
+    pub fn render(
+        &self,
+        label: &str,
+        url: &str,
+        member_count: usize,
+        generated: DateTime<Utc>,
+    ) -> String {
+        let mut output = String::new();
+        let generated = generated.format("%Y-%m-%d %H:%M:%S UTC").to_string();
+
+        output.push_str(&format!("# {} Callsign Notes for Ham2K PoLo\n", label));
+        if self.timestamp {
+            output.push_str(&format!("# Generated: {}\n", generated));
+        }
+        if !url.is_empty() {
+            output.push_str(&format!("# {}\n", url));
+        }
+        let expand = |text: &str| {
+            expand_header(text, |name| match name {
+                "label" => Some(label.to_string()),
+                "member_count" => Some(member_count.to_string()),
+                "generated" => Some(generated.clone()),
+                _ => self
+                    .metadata
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.clone()),
+            })
+        };
+        for text in self.description.iter().chain(&self.lines) {
+            output.push_str(&format!("# {}\n", expand(text)));
+        }
+        if self.do_not_edit {
+            output.push_str("# Do not edit manually - this file is auto-generated\n");
+        }
+        output.push('\n');
+        output
+    }
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self {
+            description: None,
+            lines: Vec::new(),
+            timestamp: true,
+            do_not_edit: true,
+            metadata: Vec::new(),
+        }
+    }
+}
 
 pub struct NotesGenerator {
     emoji: String,
     label: String,
     url: String,
+    former_calls: Option<FormerCalls>,
+    flags: bool,
+    license_flag: Option<String>,
+    template: LineTemplate,
+    header: Header,
+    sort: SortOrder,
 }
 
 impl NotesGenerator {
@@ -13,48 +108,174 @@ impl NotesGenerator {
             emoji,
             label,
             url: url.unwrap_or_default(),
+            former_calls: None,
+            flags: false,
+            license_flag: None,
+            template: LineTemplate::default(),
+            header: Header::default(),
+            sort: SortOrder::default(),
         }
     }
 
+    /// Also emit a line for each remapped member's roster callsign, ending
+    /// in `marker` and the current callsign (e.g. `K1OLD ... #123 → W1NEW`).
+    /// Remaps first seen longer than `max_age` ago are left out.
+    pub fn with_former_calls(mut self, marker: String, max_age: Option<Duration>) -> Self {
+        self.former_calls = Some(FormerCalls { marker, max_age });
+        self
+    }
+
+    /// Prefix each line with the member's country flag, when their DXCC
+    /// entity is known.
+    pub fn with_flags(mut self, flags: bool) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// End the lines of members with a license problem in `marker` and the
+    /// problem (e.g. `... #123 ⚠️ expired`).
+    pub fn with_license_flag(mut self, marker: String) -> Self {
+        self.license_flag = Some(marker);
+        self
+    }
+
+    /// Format member lines with `template` instead of the default.
+    pub fn with_template(mut self, template: LineTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Order lines by `sort` instead of by callsign.
+    pub fn with_sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Roster columns the line template reads.
+    pub fn roster_fields(&self) -> Vec<String> {
+        self.template.roster_fields()
+    }
+
+    /// Use `header` for the comment lines under the title.
+    pub fn with_header(mut self, header: Header) -> Self {
+        self.header = header;
+        self
+    }
+
     pub fn generate(&self, members: &[Member]) -> String {
-        let mut output = String::new();
+        self.generate_at(members, Utc::now())
+    }
 
-        // Header comments
-        output.push_str(&format!("# {} Callsign Notes for Ham2K PoLo\n", self.label));
-        output.push_str(&format!(
-            "# Generated: {}\n",
-            Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
-        ));
-        if !self.url.is_empty() {
-            output.push_str(&format!("# {}\n", self.url));
-        }
-        output.push_str("# Do not edit manually - this file is auto-generated\n");
-        output.push('\n');
+    /// Generate with `generated` as the header timestamp. The output only
+    /// depends on the arguments; the order of `members` matters only for
+    /// `SortOrder::Roster`, which is that order.
+    pub fn generate_at(&self, members: &[Member], generated: DateTime<Utc>) -> String {
+        self.generate_shard_at(members, &|_, _| true, generated)
+    }
+
+    /// Generate only the lines whose callsign `in_shard` accepts: members by
+    /// their current callsign, former-call lines by the former callsign.
+    /// `members` is still the whole roster, so a former callsign that's now
+    /// another member's current callsign is skipped whichever shard it's in.
+    pub fn generate_shard_at(
+        &self,
+        members: &[Member],
+        in_shard: &dyn Fn(&str, &Member) -> bool,
+        generated: DateTime<Utc>,
+    ) -> String {
+        let member_count = members.iter().filter(|m| in_shard(&m.callsign, m)).count();
+        let mut output = self
+            .header
+            .render(&self.label, &self.url, member_count, generated);
 
         // Sort and generate entries
-        let mut sorted: Vec<_> = members.iter().collect();
-        sorted.sort_by(|a, b| a.callsign.cmp(&b.callsign));
-
-        for member in sorted {
-            let nickname_part = member
-                .nickname
-                .as_ref()
-                .map(|n| format!("{} ", n))
-                .unwrap_or_default();
-
-            output.push_str(&format!(
-                "{} {} {}{} #{}\n",
-                member.callsign, self.emoji, nickname_part, self.label, member.member_id
-            ));
+        let mut lines: Vec<(SortKey, String)> = members
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| in_shard(&m.callsign, m))
+            .map(|(index, m)| {
+                let key = SortKey {
+                    index,
+                    member: m,
+                    callsign: &m.callsign,
+                };
+                (key, self.line(&m.callsign, m))
+            })
+            .collect();
+        lines.extend(
+            self.former_call_lines(members, generated)
+                .into_iter()
+                .filter(|(key, _)| in_shard(key.callsign, key.member)),
+        );
+        // Whole-line tie-break so equal keys can't swap between runs
+        lines.sort_by(|a, b| self.sort.compare(a.0, b.0).then_with(|| a.1.cmp(&b.1)));
+
+        for (_, line) in lines {
+            output.push_str(&line);
+            output.push('\n');
         }
 
         output
     }
+
+    fn line(&self, callsign: &str, member: &Member) -> String {
+        self.template.render(|placeholder| match placeholder {
+            Placeholder::Callsign => Some(callsign.to_string()),
+            Placeholder::Emoji => Some(self.emoji.clone()),
+            Placeholder::Label => Some(self.label.clone()),
+            Placeholder::Flag if !self.flags => None,
+            Placeholder::License => match (&self.license_flag, member.license_problem) {
+                (Some(marker), Some(problem)) => Some(format!("{} {}", marker, problem)),
+                _ => None,
+            },
+            _ => member_value(member, placeholder),
+        })
+    }
+
+    /// Lines for former callsigns, keyed by the former callsign. A former
+    /// callsign that's now some other member's current callsign is skipped,
+    /// and so is one older than the max age as of `generated`.
+    fn former_call_lines<'a>(
+        &self,
+        members: &'a [Member],
+        generated: DateTime<Utc>,
+    ) -> Vec<(SortKey<'a>, String)> {
+        let Some(former) = &self.former_calls else {
+            return Vec::new();
+        };
+        let current: HashSet<&str> = members.iter().map(|m| m.callsign.as_str()).collect();
+
+        members
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.is_remapped() && !current.contains(m.roster_callsign.as_str()))
+            .filter(|(_, m)| match (former.max_age, m.remapped_since) {
+                (Some(max_age), Some(since)) => generated - since <= max_age,
+                _ => true,
+            })
+            .map(|(index, m)| {
+                let line = format!(
+                    "{} {} {}",
+                    self.line(&m.roster_callsign, m),
+                    former.marker,
+                    m.callsign
+                );
+                let key = SortKey {
+                    index,
+                    member: m,
+                    callsign: &m.roster_callsign,
+                };
+                (key, line)
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dxcc::DxccEntity;
+    use crate::license::LicenseProblem;
 
     #[test]
     fn test_generate_notes() {
@@ -69,16 +290,19 @@ mod tests {
                 callsign: "W6JSV".to_string(),
                 member_id: "10".to_string(),
                 nickname: None,
+                ..Default::default()
             },
             Member {
                 callsign: "K4MW".to_string(),
                 member_id: "1".to_string(),
                 nickname: Some("Mike".to_string()),
+                ..Default::default()
             },
             Member {
                 callsign: "WN7JT".to_string(),
                 member_id: "2".to_string(),
                 nickname: None,
+                ..Default::default()
             },
         ];
 
@@ -113,6 +337,7 @@ mod tests {
             callsign: "W6JSV".to_string(),
             member_id: "1234".to_string(),
             nickname: None,
+            ..Default::default()
         }];
 
         let output = generator.generate(&members);
@@ -121,6 +346,216 @@ mod tests {
         assert!(output.contains("W6JSV 🎹 CWops #1234"));
     }
 
+    #[test]
+    fn test_generate_former_calls() {
+        let generator = NotesGenerator::new("🎹".to_string(), "CWops".to_string(), None)
+            .with_former_calls("→".to_string(), Some(Duration::days(365)));
+
+        let remapped = |roster: &str, current: &str, id: &str, days_ago: i64| {
+            let mut m = Member::new(roster.to_string(), id.to_string());
+            m.callsign = current.to_string();
+            m.nickname = Some("Bob".to_string());
+            m.remapped_since = Some(Utc::now() - Duration::days(days_ago));
+            m
+        };
+
+        let members = vec![
+            remapped("K1OLD", "W1NEW", "123", 30),
+            remapped("K2OLD", "W2NEW", "124", 400),
+            // K3OLD was reissued to another member; no former line for it.
+            remapped("K3OLD", "W3NEW", "125", 30),
+            Member::new("K3OLD".to_string(), "900".to_string()),
+        ];
+
+        let output = generator.generate(&members);
+        let lines: Vec<&str> = output
+            .lines()
+            .filter(|l| !l.starts_with('#') && !l.is_empty())
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "K1OLD 🎹 Bob CWops #123 → W1NEW",
+                "K3OLD 🎹 CWops #900",
+                "W1NEW 🎹 Bob CWops #123",
+                "W2NEW 🎹 Bob CWops #124",
+                "W3NEW 🎹 Bob CWops #125",
+            ]
+        );
+
+        // Expiry is measured from the generated time, not the wall clock
+        let later = generator.generate_at(&members, Utc::now() + Duration::days(400));
+        assert!(!later.contains("K1OLD 🎹 Bob CWops #123 → W1NEW"));
+    }
+
+    #[test]
+    fn test_former_calls_across_shards() {
+        let generator = NotesGenerator::new("🎹".to_string(), "CWops".to_string(), None)
+            .with_former_calls("→".to_string(), None);
+        let remapped = |roster: &str, current: &str| {
+            let mut m = Member::new(roster.to_string(), "1".to_string());
+            m.callsign = current.to_string();
+            m.remapped_since = Some(Utc::now());
+            m
+        };
+        let members = vec![
+            // W1OLD now belongs to member 2, in another shard
+            remapped("W1OLD", "K4NEW"),
+            Member::new("W1OLD".to_string(), "2".to_string()),
+            remapped("W1ABC", "K4XYZ"),
+        ];
+        let shard = |area: char| {
+            let output = generator.generate_shard_at(
+                &members,
+                &|call, _| call.chars().nth(1) == Some(area),
+                DateTime::UNIX_EPOCH,
+            );
+            output
+                .lines()
+                .filter(|l| !l.starts_with('#') && !l.is_empty())
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            shard('1'),
+            ["W1ABC 🎹 CWops #1 → K4XYZ", "W1OLD 🎹 CWops #2"]
+        );
+        assert_eq!(shard('4'), ["K4NEW 🎹 CWops #1", "K4XYZ 🎹 CWops #1"]);
+    }
+
+    #[test]
+    fn test_generate_with_flags() {
+        let generator =
+            NotesGenerator::new("🎹".to_string(), "CWops".to_string(), None).with_flags(true);
+
+        let mut hans = Member::new("DL1ABC".to_string(), "456".to_string());
+        hans.nickname = Some("Hans".to_string());
+        hans.dxcc = Some(DxccEntity {
+            name: "Fed. Rep. of Germany".to_string(),
+            primary_prefix: "DL".to_string(),
+            continent: "EU".to_string(),
+            cq_zone: 14,
+            itu_zone: 28,
+            iso_country: Some("DE".to_string()),
+        });
+        let members = vec![hans, Member::new("1S1A".to_string(), "457".to_string())];
+
+        let output = generator.generate(&members);
+        assert!(output.contains("DL1ABC 🇩🇪 🎹 Hans CWops #456\n"));
+        // Unknown entity: no flag.
+        assert!(output.contains("1S1A 🎹 CWops #457\n"));
+    }
+
+    #[test]
+    fn test_generate_with_license_flag() {
+        let generator = NotesGenerator::new("⚓".to_string(), "QRQ Crew".to_string(), None)
+            .with_license_flag("⚠️".to_string());
+        let mut expired = Member::new("K1OLD".to_string(), "7".to_string());
+        expired.license_problem = Some(LicenseProblem::Expired);
+        let members = vec![expired, Member::new("K4MW".to_string(), "1".to_string())];
+
+        let output = generator.generate(&members);
+        assert!(output.contains("K1OLD ⚓ QRQ Crew #7 ⚠️ expired\n"));
+        assert!(output.contains("K4MW ⚓ QRQ Crew #1\n"));
+    }
+
+    #[test]
+    fn test_generate_with_template() {
+        let template =
+            LineTemplate::parse("{callsign} {emoji} [{nickname} ]#{member_id}[ ({field:State})]")
+                .unwrap();
+        let generator = NotesGenerator::new("🎹".to_string(), "CWops".to_string(), None)
+            .with_template(template);
+        let mut bob = Member::new("W1ABC".to_string(), "123".to_string());
+        bob.nickname = Some("Bob".to_string());
+        bob.fields.insert("State".to_string(), "MA".to_string());
+        let members = vec![bob, Member::new("K4MW".to_string(), "1".to_string())];
+
+        let output = generator.generate(&members);
+        assert!(output.contains("W1ABC 🎹 Bob #123 (MA)\n"));
+        assert!(output.contains("K4MW 🎹 #1\n"));
+    }
+
+    #[test]
+    fn test_generate_with_header() {
+        let generator = NotesGenerator::new(
+            "⚓".to_string(),
+            "QRQ Crew".to_string(),
+            Some("https://qrqcrew.club".to_string()),
+        )
+        .with_header(Header {
+            description: Some("{label}: {member_count} members".to_string()),
+            lines: vec!["Roster: {source}".to_string(), "{unknown}".to_string()],
+            timestamp: false,
+            do_not_edit: false,
+            metadata: vec![(
+                "source".to_string(),
+                "https://example.com/roster".to_string(),
+            )],
+        });
+        let members = vec![Member::new("K4MW".to_string(), "1".to_string())];
+
+        let output = generator.generate(&members);
+        let header: Vec<&str> = output.lines().take_while(|l| !l.is_empty()).collect();
+        assert_eq!(
+            header,
+            [
+                "# QRQ Crew Callsign Notes for Ham2K PoLo",
+                "# https://qrqcrew.club",
+                "# QRQ Crew: 1 members",
+                "# Roster: https://example.com/roster",
+                "# {unknown}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_with_sort() {
+        let generator = NotesGenerator::new("🔑".to_string(), "SKCC".to_string(), None)
+            .with_sort(SortOrder::MemberId);
+        let members = vec![
+            Member::new("W1ABC".to_string(), "678C".to_string()),
+            Member::new("W10AB".to_string(), "45T".to_string()),
+            Member::new("K4MW".to_string(), "1000S".to_string()),
+        ];
+
+        let lines: Vec<String> = generator
+            .generate(&members)
+            .lines()
+            .filter(|l| !l.starts_with('#') && !l.is_empty())
+            .map(str::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "W10AB 🔑 SKCC #45T",
+                "W1ABC 🔑 SKCC #678C",
+                "K4MW 🔑 SKCC #1000S"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_at_is_deterministic() {
+        let generator = NotesGenerator::new("⚓".to_string(), "QRQ Crew".to_string(), None);
+        let generated = Utc::now() - Duration::days(3);
+        let mut members = vec![
+            Member::new("K4MW".to_string(), "1".to_string()),
+            Member::new("W6JSV".to_string(), "10".to_string()),
+            Member::new("WN7JT".to_string(), "2".to_string()),
+        ];
+
+        let first = generator.generate_at(&members, generated);
+        members.reverse();
+        assert_eq!(generator.generate_at(&members, generated), first);
+        assert!(first.contains(&format!(
+            "# Generated: {}",
+            generated.format("%Y-%m-%d %H:%M:%S UTC")
+        )));
+    }
+
     #[test]
     fn test_generate_empty() {
         let generator = NotesGenerator::new("⚓".to_string(), "Test".to_string(), None);
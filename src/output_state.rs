@@ -0,0 +1,140 @@
+//! Stable `Generated:` timestamps.
+//!
+//! Each output's content is hashed with its timestamp left out, and the
+//! timestamp is only moved forward when that hash changes. A cycle where no
+//! member changed then produces byte-identical files, which in turn lets the
+//! GitHub commit be skipped. The hashes and timestamps are kept in a small
+//! JSON file so restarts don't bump every timestamp.
+
+use crate::atomic_file::write_atomic;
+use crate::hash::fnv1a;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OutputRecord {
+    hash: String,
+    generated: DateTime<Utc>,
+}
+
+pub struct OutputState {
+    path: PathBuf,
+    outputs: HashMap<String, OutputRecord>,
+    dirty: bool,
+}
+
+impl OutputState {
+    /// Empty state, saved to `path`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            outputs: HashMap::new(),
+            dirty: false,
+        }
+    }
+
+    /// Load the state, or start empty if the file doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let outputs = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read output state: {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse output state: {}", path.display()))?
+        } else {
+            HashMap::new()
+        };
+        Ok(Self {
+            path,
+            outputs,
+            dirty: false,
+        })
+    }
+
+    /// The timestamp for output `key` with content `render(timestamp)`:
+    /// the previous one if nothing else in the content changed, `now`
+    /// otherwise. Returns the rendered content.
+    pub fn render(
+        &mut self,
+        key: &str,
+        now: DateTime<Utc>,
+        render: impl Fn(DateTime<Utc>) -> Result<String>,
+    ) -> Result<String> {
+        let hash = content_hash(&render(DateTime::UNIX_EPOCH)?);
+        let generated = match self.outputs.get(key) {
+            Some(record) if record.hash == hash => record.generated,
+            _ => {
+                self.outputs.insert(
+                    key.to_string(),
+                    OutputRecord {
+                        hash,
+                        generated: now,
+                    },
+                );
+                self.dirty = true;
+                now
+            }
+        };
+        render(generated)
+    }
+
+    /// Write the state via a temp file and rename, if anything changed.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let content = serde_json::to_string_pretty(&self.outputs)
+            .context("Failed to serialize output state")?;
+        write_atomic(&self.path, content.as_bytes()).context("Failed to write output state")?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// FNV-1a of `content`, as hex.
+fn content_hash(content: &str) -> String {
+    format!("{:016x}", fnv1a(content.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_timestamp_only_moves_when_content_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output_state.json");
+        let day1 = Utc::now() - Duration::days(1);
+        let day2 = Utc::now();
+        let render = |lines: &'static str| {
+            move |ts: DateTime<Utc>| Ok(format!("# Generated: {}\n{}", ts.timestamp(), lines))
+        };
+
+        let mut state = OutputState::load(&path).unwrap();
+        let first = state.render("notes.txt", day1, render("K4MW\n")).unwrap();
+        state.save().unwrap();
+
+        let mut state = OutputState::load(&path).unwrap();
+        assert_eq!(
+            state.render("notes.txt", day2, render("K4MW\n")).unwrap(),
+            first
+        );
+        let changed = state
+            .render("notes.txt", day2, render("K4MW\nW6JSV\n"))
+            .unwrap();
+        assert!(changed.contains(&day2.timestamp().to_string()));
+        // Keys are independent
+        let other = state.render("other.txt", day2, render("K4MW\n")).unwrap();
+        assert!(other.contains(&day2.timestamp().to_string()));
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+    }
+}
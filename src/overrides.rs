@@ -0,0 +1,407 @@
+//! Manual per-callsign overrides applied after enrichment.
+//!
+//! QRZ isn't always right for our purposes: a member may prefer a different
+//! name on the air, a remap may point at a family member's call, or a member
+//! may have asked to be left out of the notes. An overrides file fixes these
+//! by hand, either globally or per organization.
+//!
+//! TOML format:
+//!
+//! ```toml
+//! [callsigns.W1ABC]
+//! nickname = "Bob"          # force the nickname
+//!
+//! [callsigns.K1OLD]
+//! no_remap = true           # keep the roster callsign
+//!
+//! [callsigns.W6JSV]
+//! current_call = "W6JY"     # force a remap
+//!
+//! [callsigns.N0HIDE]
+//! exclude = true            # leave this member out
+//!
+//! [[extra]]                 # static entries not in the roster
+//! callsign = "W1AW"
+//! member_id = "HQ"
+//! nickname = "Hiram"
+//! ```
+//!
+//! CSV format, with a header row of `callsign,action,value` and one action
+//! per row: `nickname,<name>`, `remap,<call>`, `no_remap`, `exclude`, or
+//! `extra,<member id>`.
+//!
+//! Callsign keys match either the roster callsign or the current callsign.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::info;
+
+use crate::csv_fetcher::{Member, Source};
+
+/// Overrides for a single callsign.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct CallsignOverride {
+    /// Nickname to use instead of the looked-up one.
+    pub nickname: Option<String>,
+    /// Callsign to remap the member to, regardless of the lookup.
+    pub current_call: Option<String>,
+    /// Undo any remap and keep the roster callsign.
+    #[serde(default)]
+    pub no_remap: bool,
+    /// Leave the member out of the generated notes.
+    #[serde(default)]
+    pub exclude: bool,
+}
+
+impl CallsignOverride {
+    /// Layer `other` on top of `self`. Values set in `other` win; flags can
+    /// only be turned on.
+    fn merge(&mut self, other: &CallsignOverride) {
+        if other.nickname.is_some() {
+            self.nickname = other.nickname.clone();
+        }
+        if other.current_call.is_some() {
+            self.current_call = other.current_call.clone();
+        }
+        self.no_remap |= other.no_remap;
+        self.exclude |= other.exclude;
+    }
+}
+
+/// A static entry added to the notes even though it isn't in the roster,
+/// e.g. a club station call.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ExtraEntry {
+    pub callsign: String,
+    pub member_id: String,
+    pub nickname: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Overrides {
+    #[serde(default)]
+    callsigns: HashMap<String, CallsignOverride>,
+    #[serde(default)]
+    extra: Vec<ExtraEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CsvRow {
+    callsign: String,
+    action: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+impl Overrides {
+    /// Load an overrides file. The format is picked by extension: `.csv`
+    /// for CSV, anything else is parsed as TOML.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read overrides file: {}", path.display()))?;
+
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let overrides = if is_csv {
+            Self::parse_csv(&content)
+        } else {
+            Self::parse_toml(&content)
+        };
+        overrides.with_context(|| format!("Failed to parse overrides file: {}", path.display()))
+    }
+
+    fn parse_toml(content: &str) -> Result<Self> {
+        let parsed: Overrides = toml::from_str(content)?;
+        Ok(parsed.normalized())
+    }
+
+    fn parse_csv(content: &str) -> Result<Self> {
+        let mut overrides = Overrides::default();
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .from_reader(content.as_bytes());
+
+        for (idx, row) in reader.deserialize::<CsvRow>().enumerate() {
+            let row = row.with_context(|| format!("Invalid overrides row {}", idx + 2))?;
+            let value = row.value.filter(|v| !v.is_empty());
+            let require_value = || {
+                value.clone().with_context(|| {
+                    format!(
+                        "Overrides row {}: action '{}' needs a value",
+                        idx + 2,
+                        row.action
+                    )
+                })
+            };
+
+            let action = row.action.to_lowercase();
+            if action == "extra" {
+                overrides.extra.push(ExtraEntry {
+                    callsign: row.callsign.clone(),
+                    member_id: require_value()?,
+                    nickname: None,
+                });
+                continue;
+            }
+
+            let entry = overrides.callsigns.entry(row.callsign.clone()).or_default();
+            match action.as_str() {
+                "nickname" => entry.nickname = Some(require_value()?),
+                "remap" => entry.current_call = Some(require_value()?),
+                "no_remap" => entry.no_remap = true,
+                "exclude" => entry.exclude = true,
+                other => anyhow::bail!("Overrides row {}: unknown action '{}'", idx + 2, other),
+            }
+        }
+
+        Ok(overrides.normalized())
+    }
+
+    /// Uppercase callsign keys and values so lookups are case-insensitive.
+    fn normalized(self) -> Self {
+        Self {
+            callsigns: self
+                .callsigns
+                .into_iter()
+                .map(|(call, mut o)| {
+                    o.current_call = o.current_call.map(|c| c.trim().to_uppercase());
+                    (call.trim().to_uppercase(), o)
+                })
+                .collect(),
+            extra: self
+                .extra
+                .into_iter()
+                .map(|mut e| {
+                    e.callsign = e.callsign.trim().to_uppercase();
+                    e
+                })
+                .collect(),
+        }
+    }
+
+    /// Combine global and per-org overrides; per-org values win.
+    pub fn merged(global: Option<Overrides>, org: Option<Overrides>) -> Overrides {
+        let mut merged = global.unwrap_or_default();
+        if let Some(org) = org {
+            for (call, o) in org.callsigns {
+                merged.callsigns.entry(call).or_default().merge(&o);
+            }
+            for extra in org.extra {
+                merged.extra.retain(|e| e.callsign != extra.callsign);
+                merged.extra.push(extra);
+            }
+        }
+        merged
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.callsigns.is_empty() && self.extra.is_empty()
+    }
+
+    /// Override for a member: the entry keyed by its roster callsign, with
+    /// the entry keyed by its current callsign layered on top.
+    fn for_member(&self, member: &Member) -> Option<CallsignOverride> {
+        let roster = member.roster_callsign.to_uppercase();
+        let current = member.callsign.to_uppercase();
+        let mut combined: Option<CallsignOverride> = self.callsigns.get(&roster).cloned();
+        if current != roster
+            && let Some(o) = self.callsigns.get(&current)
+        {
+            combined.get_or_insert_with(Default::default).merge(o);
+        }
+        combined
+    }
+
+    /// Apply overrides to enriched members, logging each change. Returns the
+    /// number of overrides applied.
+    pub fn apply(&self, members: &mut Vec<Member>, org_name: &str) -> usize {
+        let mut applied = 0;
+
+        for extra in &self.extra {
+            if members.iter().any(|m| m.callsign == extra.callsign) {
+                continue;
+            }
+            info!(
+                "[{}] Override: adding extra entry {} #{}",
+                org_name, extra.callsign, extra.member_id
+            );
+            let mut member = Member::new(extra.callsign.clone(), extra.member_id.clone());
+            member.nickname = extra.nickname.clone();
+            member.nickname_source = member.nickname.as_ref().map(|_| Source::Override);
+            members.push(member);
+            applied += 1;
+        }
+
+        members.retain_mut(|member| {
+            let Some(o) = self.for_member(member) else {
+                return true;
+            };
+
+            if o.exclude {
+                info!("[{}] Override: excluding {}", org_name, member.callsign);
+                applied += 1;
+                return false;
+            }
+
+            if o.no_remap && member.is_remapped() {
+                // The nickname may have come from the rejected record, so
+                // fall back to the roster's own name, if any.
+                info!(
+                    "[{}] Override: keeping roster callsign {} instead of {}",
+                    org_name, member.roster_callsign, member.callsign
+                );
+                member.callsign = member.roster_callsign.clone();
+                member.remapped_since = None;
+                member.nickname = member.roster_nickname.clone();
+                member.nickname_source = member.nickname.as_ref().map(|_| Source::Roster);
+                member.remap_source = None;
+                applied += 1;
+            }
+
+            if let Some(call) = &o.current_call
+                && *call != member.callsign
+            {
+                info!(
+                    "[{}] Override: remapping {} -> {}",
+                    org_name, member.callsign, call
+                );
+                // A different remap than the lookup's, so not its date
+                member.callsign = call.clone();
+                member.remapped_since = None;
+                member.remap_source = member.is_remapped().then_some(Source::Override);
+                applied += 1;
+            }
+
+            if let Some(nick) = &o.nickname
+                && member.nickname.as_ref() != Some(nick)
+            {
+                info!(
+                    "[{}] Override: nickname for {} set to {}",
+                    org_name, member.callsign, nick
+                );
+                member.nickname = Some(nick.clone());
+                member.nickname_source = Some(Source::Override);
+                applied += 1;
+            }
+
+            true
+        });
+
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remapped(roster: &str, current: &str, id: &str, nick: Option<&str>) -> Member {
+        let mut m = Member::new(roster.to_string(), id.to_string());
+        m.callsign = current.to_string();
+        m.nickname = nick.map(|n| n.to_string());
+        m
+    }
+
+    #[test]
+    fn test_toml_overrides() {
+        let overrides = Overrides::parse_toml(
+            r#"
+[callsigns.w1abc]
+nickname = "Bob"
+
+[callsigns.K1OLD]
+no_remap = true
+
+[callsigns.W6JSV]
+current_call = "w6jy"
+
+[callsigns.N0HIDE]
+exclude = true
+
+[[extra]]
+callsign = "w1aw"
+member_id = "HQ"
+nickname = "Hiram"
+"#,
+        )
+        .unwrap();
+
+        let mut members = vec![
+            remapped("W1ABC", "W1ABC", "1", Some("Robert")),
+            remapped("K1OLD", "K1NEW", "2", Some("Alice")),
+            remapped("W6JSV", "W6JSV", "3", None),
+            remapped("N0HIDE", "N0HIDE", "4", None),
+        ];
+        let applied = overrides.apply(&mut members, "test");
+        assert_eq!(applied, 5);
+
+        let calls: Vec<_> = members.iter().map(|m| m.callsign.as_str()).collect();
+        assert_eq!(calls, vec!["W1ABC", "K1OLD", "W6JY", "W1AW"]);
+        assert_eq!(members[0].nickname.as_deref(), Some("Bob"));
+        assert_eq!(members[1].nickname, None);
+        assert_eq!(members[3].member_id, "HQ");
+        assert_eq!(members[3].nickname.as_deref(), Some("Hiram"));
+    }
+
+    #[test]
+    fn test_csv_overrides() {
+        let overrides = Overrides::parse_csv(
+            "callsign,action,value\n\
+             W1ABC,nickname,Bob\n\
+             K1OLD,no_remap,\n\
+             N0HIDE,exclude\n\
+             W1AW,extra,HQ\n\
+             W1AW,nickname,Hiram\n",
+        )
+        .unwrap();
+
+        let mut members = vec![
+            remapped("W1ABC", "W1ABC", "1", None),
+            remapped("K1OLD", "K1NEW", "2", None),
+            remapped("N0HIDE", "N0HIDE", "3", None),
+        ];
+        overrides.apply(&mut members, "test");
+
+        let calls: Vec<_> = members.iter().map(|m| m.callsign.as_str()).collect();
+        assert_eq!(calls, vec!["W1ABC", "K1OLD", "W1AW"]);
+        assert_eq!(members[2].nickname.as_deref(), Some("Hiram"));
+    }
+
+    #[test]
+    fn test_csv_rejects_unknown_action() {
+        let err = Overrides::parse_csv("callsign,action,value\nW1ABC,rename,Bob\n").unwrap_err();
+        assert!(err.to_string().contains("unknown action"));
+    }
+
+    #[test]
+    fn test_override_matches_current_callsign() {
+        let overrides = Overrides::parse_toml("[callsigns.K1NEW]\nnickname = \"Al\"\n").unwrap();
+        let mut members = vec![remapped("K1OLD", "K1NEW", "2", Some("Alice"))];
+        overrides.apply(&mut members, "test");
+        assert_eq!(members[0].nickname.as_deref(), Some("Al"));
+    }
+
+    #[test]
+    fn test_org_overrides_win_over_global() {
+        let global = Overrides::parse_toml(
+            "[callsigns.W1ABC]\nnickname = \"Bob\"\nexclude = false\n[[extra]]\ncallsign = \"W1AW\"\nmember_id = \"1\"\n",
+        )
+        .unwrap();
+        let org = Overrides::parse_toml(
+            "[callsigns.W1ABC]\nnickname = \"Bobby\"\n[[extra]]\ncallsign = \"W1AW\"\nmember_id = \"HQ\"\n",
+        )
+        .unwrap();
+        let merged = Overrides::merged(Some(global), Some(org));
+
+        let mut members = vec![remapped("W1ABC", "W1ABC", "1", None)];
+        merged.apply(&mut members, "test");
+        assert_eq!(members[0].nickname.as_deref(), Some("Bobby"));
+        assert_eq!(members[1].member_id, "HQ");
+        assert_eq!(members.len(), 2);
+    }
+}
@@ -4,11 +4,29 @@
 //! the response) and their first name nickname (from `<fname>`). Querying an
 //! old callsign returns the new one in `<call>`, so we use that as the
 //! authoritative current callsign and remap roster entries accordingly.
+//!
+//! Every response also carries a `<Session>` block with the account's
+//! lookup count for the current 24 hour window (`<Count>`) and the
+//! subscription expiry (`<SubExp>`). The latest values are kept on the
+//! client so callers can pace lookups against the daily quota.
+//!
+//! The API accepts POSTed form fields as well as a query string, so login
+//! and lookups are sent as POST bodies. That keeps the password and the
+//! session key out of URLs, which reqwest includes in its error messages.
 
+use crate::redact;
 use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::debug;
+use tracing::{debug, info, warn};
+
+/// Default number of days before subscription expiry to start warning.
+const DEFAULT_EXPIRY_WARNING_DAYS: i64 = 14;
+
+const QRZ_XML_URL: &str = "https://xmldata.qrz.com/xml/current/";
+
+const AGENT: &str = "qrqcrew-notes-daemon";
 
 /// Result of a QRZ callsign lookup.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -19,6 +37,52 @@ pub struct QrzInfo {
     pub current_call: String,
     /// The operator's preferred first name from `<fname>`, if present.
     pub nickname: Option<String>,
+    /// License expiration date from `<expdate>`, when QRZ has one.
+    pub license_expires: Option<NaiveDate>,
+}
+
+/// Errors callers need to tell apart from transient failures.
+#[derive(Debug, thiserror::Error)]
+pub enum QrzError {
+    /// Login was rejected (bad credentials, suspended account, ...).
+    /// Retrying won't help until the configuration changes.
+    #[error("QRZ authentication failed: {0}")]
+    Auth(String),
+}
+
+impl QrzError {
+    /// True when `err` (or anything in its chain) is an authentication error.
+    pub fn is_auth(err: &anyhow::Error) -> bool {
+        matches!(err.downcast_ref::<QrzError>(), Some(QrzError::Auth(_)))
+    }
+}
+
+/// Subscription details reported in the `<Session>` block of a response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionStatus {
+    /// Lookups performed by this account in the current 24 hour window.
+    pub count: Option<u64>,
+    /// UTC day `count` was reported on.
+    pub count_day: Option<NaiveDate>,
+    /// Raw `<SubExp>` value, e.g. `Wed Jan 1 12:34:03 2025` or
+    /// `non-subscriber`.
+    pub sub_exp: Option<String>,
+}
+
+impl SessionStatus {
+    /// Parse `<SubExp>` into a timestamp. Returns `None` for
+    /// `non-subscriber` or anything else QRZ might put there.
+    pub fn expires_at(&self) -> Option<NaiveDateTime> {
+        let raw = self.sub_exp.as_deref()?.trim();
+        NaiveDateTime::parse_from_str(raw, "%a %b %e %H:%M:%S %Y").ok()
+    }
+
+    /// True when QRZ reports the account has no XML subscription.
+    pub fn is_non_subscriber(&self) -> bool {
+        self.sub_exp
+            .as_deref()
+            .is_some_and(|s| s.trim().eq_ignore_ascii_case("non-subscriber"))
+    }
 }
 
 /// QRZ API client with session caching
@@ -28,38 +92,112 @@ pub struct QrzClient {
     password: String,
     http: reqwest::Client,
     session_key: Arc<RwLock<Option<String>>>,
+    status: Arc<RwLock<SessionStatus>>,
+    expiry_warning: Duration,
 }
 
 impl QrzClient {
     pub fn new(username: String, password: String) -> Self {
+        redact::register_secret(&password);
         Self {
             username,
             password,
             http: reqwest::Client::new(),
             session_key: Arc::new(RwLock::new(None)),
+            status: Arc::new(RwLock::new(SessionStatus::default())),
+            expiry_warning: Duration::days(DEFAULT_EXPIRY_WARNING_DAYS),
         }
     }
 
+    /// Warn at login when the subscription expires within `days`.
+    pub fn with_expiry_warning_days(mut self, days: i64) -> Self {
+        self.expiry_warning = Duration::days(days);
+        self
+    }
+
+    /// Latest session status seen in any QRZ response.
+    pub async fn session_status(&self) -> SessionStatus {
+        self.status.read().await.clone()
+    }
+
     /// Login to QRZ and get session key
     async fn login(&self) -> Result<String> {
-        let url = format!(
-            "https://xmldata.qrz.com/xml/current/?username={}&password={}&agent=qrqcrew-notes-daemon",
-            self.username, self.password
-        );
-
         let response = self
             .http
-            .get(&url)
+            .post(QRZ_XML_URL)
+            .form(&[
+                ("username", self.username.as_str()),
+                ("password", self.password.as_str()),
+                ("agent", AGENT),
+            ])
             .send()
             .await
+            .map_err(reqwest::Error::without_url)
             .context("QRZ login request failed")?;
 
         let text = response
             .text()
             .await
+            .map_err(reqwest::Error::without_url)
             .context("QRZ login response read failed")?;
 
-        Self::extract_session_key(&text)
+        let key = Self::extract_session_key(&text)?;
+        redact::set_session_key(&key);
+        let status = Self::extract_session_status(&text);
+        self.log_subscription(&status);
+        self.record_status(status).await;
+
+        Ok(key)
+    }
+
+    /// Log lookup count and subscription expiry, warning when the
+    /// subscription is about to lapse.
+    fn log_subscription(&self, status: &SessionStatus) {
+        info!(
+            "QRZ session established: {} lookups in last 24h, subscription expires {}",
+            status
+                .count
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            status.sub_exp.as_deref().unwrap_or("unknown")
+        );
+
+        if status.is_non_subscriber() {
+            warn!("QRZ account has no XML subscription; lookups will return limited data");
+            return;
+        }
+
+        if let Some(expires) = status.expires_at() {
+            let remaining = expires - Utc::now().naive_utc();
+            if remaining <= Duration::zero() {
+                warn!("QRZ subscription expired on {}", expires);
+            } else if remaining <= self.expiry_warning {
+                warn!(
+                    "QRZ subscription expires in {} day(s) ({})",
+                    remaining.num_days(),
+                    expires
+                );
+            }
+        }
+    }
+
+    async fn record_status(&self, status: SessionStatus) {
+        let mut current = self.status.write().await;
+        if status.count.is_some() {
+            current.count = status.count;
+            current.count_day = Some(Utc::now().date_naive());
+        }
+        if status.sub_exp.is_some() {
+            current.sub_exp = status.sub_exp;
+        }
+    }
+
+    fn extract_session_status(xml: &str) -> SessionStatus {
+        SessionStatus {
+            count: Self::extract_tag(xml, "Count").and_then(|c| c.trim().parse().ok()),
+            count_day: None,
+            sub_exp: Self::extract_tag(xml, "SubExp"),
+        }
     }
 
     fn extract_session_key(xml: &str) -> Result<String> {
@@ -75,7 +213,7 @@ impl QrzClient {
             && let Some(end) = xml.find("</Error>")
         {
             let error = &xml[start + 7..end];
-            anyhow::bail!("QRZ error: {}", error);
+            return Err(QrzError::Auth(error.to_string()).into());
         }
 
         anyhow::bail!("Could not parse QRZ session key")
@@ -120,23 +258,28 @@ impl QrzClient {
 
         let session_key = self.get_session_key().await?;
 
-        let url = format!(
-            "https://xmldata.qrz.com/xml/current/?s={}&callsign={}",
-            session_key, callsign
-        );
-
         let response = self
             .http
-            .get(&url)
+            .post(QRZ_XML_URL)
+            .form(&[
+                ("s", session_key.as_str()),
+                ("callsign", callsign),
+                ("agent", AGENT),
+            ])
             .send()
             .await
+            .map_err(reqwest::Error::without_url)
             .context("QRZ lookup request failed")?;
 
         let text = response
             .text()
             .await
+            .map_err(reqwest::Error::without_url)
             .context("QRZ lookup response read failed")?;
 
+        self.record_status(Self::extract_session_status(&text))
+            .await;
+
         // Session expired — clear cache and retry once.
         if text.contains("Session Timeout") || text.contains("Invalid session key") {
             debug!("QRZ session expired, refreshing...");
@@ -155,10 +298,12 @@ impl QrzClient {
             .map(|c| c.to_uppercase())
             .unwrap_or_else(|| callsign.to_uppercase());
         let nickname = Self::extract_fname(&text);
+        let license_expires = Self::extract_expdate(&text);
 
         Ok(Some(QrzInfo {
             current_call,
             nickname,
+            license_expires,
         }))
     }
 
@@ -170,6 +315,12 @@ impl QrzClient {
         Self::extract_tag(xml, "fname")
     }
 
+    /// `<expdate>` is `YYYY-MM-DD`; QRZ uses `0000-00-00` when it's unknown.
+    fn extract_expdate(xml: &str) -> Option<NaiveDate> {
+        Self::extract_tag(xml, "expdate")
+            .and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok())
+    }
+
     /// Extract the first occurrence of `<tag>...</tag>` returning the inner
     /// text, or `None` if the tag is absent or empty.
     fn extract_tag(xml: &str, tag: &str) -> Option<String> {
@@ -216,14 +367,48 @@ mod tests {
 
         let result = QrzClient::extract_session_key(xml);
         assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("Invalid username/password")
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("Invalid username/password"));
+        assert!(QrzError::is_auth(&err.context("QRZ login failed")));
+    }
+
+    #[test]
+    fn test_extract_session_status() {
+        let xml = r#"<?xml version="1.0" ?>
+<QRZDatabase>
+  <Session>
+    <Key>abc123sessionkey</Key>
+    <Count>1234</Count>
+    <SubExp>Wed Jan  1 12:34:03 2031</SubExp>
+  </Session>
+</QRZDatabase>"#;
+
+        let status = QrzClient::extract_session_status(xml);
+        assert_eq!(status.count, Some(1234));
+        assert!(!status.is_non_subscriber());
+        assert_eq!(
+            status.expires_at().unwrap().to_string(),
+            "2031-01-01 12:34:03"
         );
     }
 
+    #[test]
+    fn test_extract_session_status_non_subscriber() {
+        let xml = r#"<?xml version="1.0" ?>
+<QRZDatabase>
+  <Session>
+    <Key>abc123sessionkey</Key>
+    <Count>7</Count>
+    <SubExp>non-subscriber</SubExp>
+  </Session>
+</QRZDatabase>"#;
+
+        let status = QrzClient::extract_session_status(xml);
+        assert_eq!(status.count, Some(7));
+        assert!(status.is_non_subscriber());
+        assert_eq!(status.expires_at(), None);
+    }
+
     #[test]
     fn test_extract_fname() {
         let xml = r#"<?xml version="1.0" ?>
@@ -281,4 +466,16 @@ mod tests {
 
         assert_eq!(QrzClient::extract_call(xml), Some("W6JY".to_string()));
     }
+
+    #[test]
+    fn test_extract_expdate() {
+        let xml = "<Callsign><call>K4MW</call><expdate>2031-04-12</expdate></Callsign>";
+        assert_eq!(
+            QrzClient::extract_expdate(xml),
+            NaiveDate::from_ymd_opt(2031, 4, 12)
+        );
+        let xml = "<Callsign><call>DL1ABC</call><expdate>0000-00-00</expdate></Callsign>";
+        assert_eq!(QrzClient::extract_expdate(xml), None);
+        assert_eq!(QrzClient::extract_expdate("<call>K4MW</call>"), None);
+    }
 }
@@ -0,0 +1,396 @@
+//! Persistent history of callsign remaps, per organization.
+//!
+//! The lookup cache only keeps the latest mapping for a callsign, and the
+//! remap itself is just a log line. This records every `old -> new` remap
+//! applied to a roster row, with the member id and when it was first and
+//! last seen, so roster maintainers can be told which members changed
+//! calls. The history is a JSON file rewritten (atomically) after each
+//! cycle, and is rendered into a CSV or Markdown report per org.
+//!
+//! Remaps rejected by the identity check are kept here too. Once rejected,
+//! an `old -> new` pair stays rejected (the check may have lost its
+//! evidence by the next cycle), until an override forces the remap.
+
+use crate::atomic_file::write_atomic;
+use crate::csv_fetcher::Member;
+use crate::report::{render_table, yes_no};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// One observed remap of a roster callsign.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemapRecord {
+    pub member_id: String,
+    pub old_call: String,
+    pub new_call: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// A remap that failed the identity check: the QRZ record for the roster
+/// call names someone other than the member.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedRemap {
+    pub member_id: String,
+    pub old_call: String,
+    pub new_call: String,
+    /// Name from the roster, or cached for the roster call.
+    pub expected_name: String,
+    pub qrz_name: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryData {
+    /// Records keyed by org name.
+    orgs: BTreeMap<String, Vec<RemapRecord>>,
+    /// Rejected remaps keyed by org name.
+    #[serde(default)]
+    rejected: BTreeMap<String, Vec<RejectedRemap>>,
+}
+
+pub struct RemapHistory {
+    path: PathBuf,
+    data: HistoryData,
+    dirty: bool,
+}
+
+impl RemapHistory {
+    /// Load the history file, or start empty if it doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let data = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read remap history: {}", path.display()))?;
+            if content.trim().is_empty() {
+                HistoryData::default()
+            } else {
+                serde_json::from_str(&content)
+                    .with_context(|| format!("Failed to parse remap history: {}", path.display()))?
+            }
+        } else {
+            debug!("No remap history file, starting fresh");
+            HistoryData::default()
+        };
+
+        Ok(Self {
+            path,
+            data,
+            dirty: false,
+        })
+    }
+
+    /// Record the remapped members of one org's roster as seen at `now`.
+    /// A remap seen before keeps its first-seen date; a new one starts at
+    /// the member's `remapped_since` when known. A member without one (a
+    /// remap forced by an override) gets the record's first-seen date, so
+    /// its age is tracked across cycles like a looked-up remap.
+    pub fn observe(&mut self, org: &str, members: &mut [Member], now: DateTime<Utc>) {
+        let records = self.data.orgs.entry(org.to_string()).or_default();
+        for member in members.iter_mut().filter(|m| m.is_remapped()) {
+            let existing = records
+                .iter_mut()
+                .find(|r| r.old_call == member.roster_callsign && r.new_call == member.callsign);
+            let first_seen = match existing {
+                Some(record) => {
+                    record.last_seen = now;
+                    record.member_id = member.member_id.clone();
+                    record.first_seen
+                }
+                None => {
+                    let first_seen = member.remapped_since.unwrap_or(now).min(now);
+                    records.push(RemapRecord {
+                        member_id: member.member_id.clone(),
+                        old_call: member.roster_callsign.clone(),
+                        new_call: member.callsign.clone(),
+                        first_seen,
+                        last_seen: now,
+                    });
+                    first_seen
+                }
+            };
+            member.remapped_since.get_or_insert(first_seen);
+        }
+        self.dirty = true;
+    }
+
+    /// All remaps recorded for an org, newest first.
+    pub fn records(&self, org: &str) -> Vec<&RemapRecord> {
+        let mut records: Vec<&RemapRecord> = self
+            .data
+            .orgs
+            .get(org)
+            .map(|r| r.iter().collect())
+            .unwrap_or_default();
+        records.sort_by(|a, b| {
+            b.first_seen
+                .cmp(&a.first_seen)
+                .then_with(|| a.old_call.cmp(&b.old_call))
+        });
+        records
+    }
+
+    /// If `old -> new` was rejected by the identity check before, mark it
+    /// seen at `now` and return true.
+    pub fn recheck_rejection(
+        &mut self,
+        org: &str,
+        old_call: &str,
+        new_call: &str,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let Some(record) = self.data.rejected.get_mut(org).and_then(|records| {
+            records
+                .iter_mut()
+                .find(|r| r.old_call == old_call && r.new_call == new_call)
+        }) else {
+            return false;
+        };
+        record.last_seen = now;
+        self.dirty = true;
+        true
+    }
+
+    /// Record a rejected remap, or refresh an existing one's last-seen date.
+    pub fn reject(&mut self, org: &str, rejection: RejectedRemap) {
+        let records = self.data.rejected.entry(org.to_string()).or_default();
+        match records
+            .iter_mut()
+            .find(|r| r.old_call == rejection.old_call && r.new_call == rejection.new_call)
+        {
+            Some(record) => {
+                record.member_id = rejection.member_id;
+                record.last_seen = rejection.last_seen;
+            }
+            None => records.push(rejection),
+        }
+        self.dirty = true;
+    }
+
+    /// All rejected remaps for an org, newest first.
+    pub fn rejections(&self, org: &str) -> Vec<&RejectedRemap> {
+        let mut records: Vec<&RejectedRemap> = self
+            .data
+            .rejected
+            .get(org)
+            .map(|r| r.iter().collect())
+            .unwrap_or_default();
+        records.sort_by(|a, b| {
+            b.first_seen
+                .cmp(&a.first_seen)
+                .then_with(|| a.old_call.cmp(&b.old_call))
+        });
+        records
+    }
+
+    /// Render an org's report. `.csv` paths get CSV, anything else gets
+    /// Markdown. A remap is "current" if it was seen in the cycle that
+    /// started at `cycle_start`.
+    ///
+    /// The report carries no generation timestamp, so it only changes when
+    /// the set of remaps does.
+    pub fn report(
+        &self,
+        org: &str,
+        label: &str,
+        path: &str,
+        cycle_start: DateTime<Utc>,
+    ) -> Result<String> {
+        let rows = self
+            .records(org)
+            .into_iter()
+            .map(|r| {
+                vec![
+                    r.member_id.clone(),
+                    r.old_call.clone(),
+                    r.new_call.clone(),
+                    r.first_seen.format("%Y-%m-%d").to_string(),
+                    yes_no(r.last_seen >= cycle_start),
+                ]
+            })
+            .collect();
+        render_table(
+            path,
+            &format!("{} Callsign Changes", label),
+            "Members whose roster callsign resolves to a different current callsign. \
+             Rows marked current are still being remapped; update the roster to stop it.",
+            &[
+                ("member_id", "Member"),
+                ("old_call", "Roster call"),
+                ("new_call", "Current call"),
+                ("first_seen", "First seen"),
+                ("current", "Current"),
+            ],
+            rows,
+        )
+    }
+
+    /// Render an org's rejected remaps for review, in the same formats as
+    /// [`RemapHistory::report`].
+    pub fn review_report(
+        &self,
+        org: &str,
+        label: &str,
+        path: &str,
+        cycle_start: DateTime<Utc>,
+    ) -> Result<String> {
+        let rows = self
+            .rejections(org)
+            .into_iter()
+            .map(|r| {
+                vec![
+                    r.member_id.clone(),
+                    r.old_call.clone(),
+                    r.new_call.clone(),
+                    r.expected_name.clone(),
+                    r.qrz_name.clone(),
+                    r.first_seen.format("%Y-%m-%d").to_string(),
+                    yes_no(r.last_seen >= cycle_start),
+                ]
+            })
+            .collect();
+        render_table(
+            path,
+            &format!("{} Remaps Needing Review", label),
+            "QRZ points these roster callsigns at an operator whose name doesn't match \
+             the member's, so the roster callsign was kept. If a remap is right, force \
+             it with a `current_call` override.",
+            &[
+                ("member_id", "Member"),
+                ("old_call", "Roster call"),
+                ("new_call", "QRZ call"),
+                ("expected_name", "Member name"),
+                ("qrz_name", "QRZ name"),
+                ("first_seen", "First seen"),
+                ("current", "Current"),
+            ],
+            rows,
+        )
+    }
+
+    /// Write the history back if anything was observed.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let content =
+            serde_json::to_string_pretty(&self.data).context("Failed to serialize history")?;
+        write_atomic(&self.path, content.as_bytes()).context("Failed to write remap history")?;
+
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn remapped(roster: &str, current: &str, id: &str) -> Member {
+        let mut m = Member::new(roster.to_string(), id.to_string());
+        m.callsign = current.to_string();
+        m
+    }
+
+    #[test]
+    fn test_observe_keeps_first_seen_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        let day1 = Utc::now() - Duration::days(10);
+        let day2 = Utc::now();
+
+        let mut history = RemapHistory::load(&path).unwrap();
+        let mut members = vec![
+            remapped("K1OLD", "W1NEW", "123"),
+            Member::new("K4MW".to_string(), "1".to_string()),
+        ];
+        history.observe("cwops", &mut members, day1);
+        assert_eq!(members[0].remapped_since, Some(day1));
+        assert_eq!(members[1].remapped_since, None);
+
+        // An override remap has no lookup date; it keeps the first sighting
+        let mut members = vec![remapped("K1OLD", "W1NEW", "123")];
+        history.observe("cwops", &mut members, day2);
+        assert_eq!(members[0].remapped_since, Some(day1));
+        history.save().unwrap();
+
+        let history = RemapHistory::load(&path).unwrap();
+        let records = history.records("cwops");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].old_call, "K1OLD");
+        assert_eq!(records[0].first_seen, day1);
+        assert_eq!(records[0].last_seen, day2);
+        assert!(history.records("qrqcrew").is_empty());
+    }
+
+    #[test]
+    fn test_reports() {
+        let mut history = RemapHistory::load("/nonexistent/history.json").unwrap();
+        let start = Utc::now() - Duration::days(30);
+        history.observe("cwops", &mut [remapped("K2OLD", "W2NEW", "124")], start);
+        let cycle = Utc::now();
+        history.observe("cwops", &mut [remapped("K1OLD", "W1NEW", "123")], cycle);
+
+        let md = history
+            .report("cwops", "CWops", "changes.md", cycle)
+            .unwrap();
+        assert!(md.starts_with("# CWops Callsign Changes"));
+        let rows: Vec<&str> = md.lines().filter(|l| l.starts_with("| 12")).collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].starts_with("| 123 | K1OLD | W1NEW |") && rows[0].ends_with("| yes |"));
+        assert!(rows[1].starts_with("| 124 | K2OLD | W2NEW |") && rows[1].ends_with("| no |"));
+
+        let csv = history
+            .report("cwops", "CWops", "changes.csv", cycle)
+            .unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("member_id,old_call,new_call,first_seen,current")
+        );
+        assert!(lines.next().unwrap().starts_with("123,K1OLD,W1NEW,"));
+    }
+
+    #[test]
+    fn test_rejections_stick_and_report() {
+        let mut history = RemapHistory::load("/nonexistent/history.json").unwrap();
+        let now = Utc::now();
+        let rejection = RejectedRemap {
+            member_id: "123".to_string(),
+            old_call: "K1OLD".to_string(),
+            new_call: "W9XYZ".to_string(),
+            expected_name: "Bob".to_string(),
+            qrz_name: "Alice".to_string(),
+            first_seen: now - Duration::days(3),
+            last_seen: now - Duration::days(3),
+        };
+        history.reject("cwops", rejection.clone());
+        history.reject(
+            "cwops",
+            RejectedRemap {
+                last_seen: now,
+                ..rejection
+            },
+        );
+
+        assert!(history.recheck_rejection("cwops", "K1OLD", "W9XYZ", now));
+        assert!(!history.recheck_rejection("cwops", "K1OLD", "W1NEW", now));
+        assert!(!history.recheck_rejection("skcc", "K1OLD", "W9XYZ", now));
+        assert_eq!(history.rejections("cwops").len(), 1);
+        // Rejections aren't remaps.
+        assert!(history.records("cwops").is_empty());
+
+        let md = history
+            .review_report("cwops", "CWops", "review.md", now)
+            .unwrap();
+        assert!(md.contains("| 123 | K1OLD | W9XYZ | Bob | Alice |"));
+        assert!(md.trim_end().ends_with("| yes |"));
+    }
+}
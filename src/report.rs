@@ -0,0 +1,66 @@
+//! Small tables published next to the notes for club maintainers (callsign
+//! changes, remaps to review, license problems). Each is written as CSV or
+//! Markdown depending on the configured path, and carries no generation
+//! timestamp so it only changes when its rows do.
+
+use anyhow::{Context, Result};
+
+pub fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+/// Render rows as CSV (for `.csv` paths) or a Markdown table. Columns are
+/// `(csv header, markdown header)` pairs.
+pub fn render_table(
+    path: &str,
+    title: &str,
+    intro: &str,
+    columns: &[(&str, &str)],
+    rows: Vec<Vec<String>>,
+) -> Result<String> {
+    if path.to_lowercase().ends_with(".csv") {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(columns.iter().map(|(csv, _)| csv))?;
+        for row in &rows {
+            writer.write_record(row)?;
+        }
+        return String::from_utf8(writer.into_inner()?).context("CSV report is not UTF-8");
+    }
+
+    let mut out = format!("# {}\n\n{}\n\n", title, intro);
+    let headers: Vec<&str> = columns.iter().map(|(_, md)| *md).collect();
+    out.push_str(&format!("| {} |\n", headers.join(" | ")));
+    out.push_str(&format!("|{}\n", "---|".repeat(columns.len())));
+    for row in &rows {
+        let cells: Vec<String> = row.iter().map(|cell| markdown_cell(cell)).collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    Ok(out)
+}
+
+/// A value made safe for a Markdown table cell: `|` escaped and line
+/// breaks turned into spaces, so roster text can't split the row.
+fn markdown_cell(value: &str) -> String {
+    value
+        .replace('|', "\\|")
+        .replace("\r\n", " ")
+        .replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_cells_are_escaped() {
+        let columns = [("call", "Call"), ("name", "Name")];
+        let rows = vec![vec!["K4MW".to_string(), "Bob | Robert\nSmith".to_string()]];
+
+        // CSV quotes the cell as is
+        let csv = render_table("r.csv", "T", "I", &columns, rows.clone()).unwrap();
+        assert!(csv.contains("\"Bob | Robert\nSmith\""));
+
+        let md = render_table("r.md", "T", "I", &columns, rows).unwrap();
+        assert!(md.ends_with("| Call | Name |\n|---|---|\n| K4MW | Bob \\| Robert Smith |\n"));
+    }
+}
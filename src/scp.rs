@@ -0,0 +1,40 @@
+//! Super Check Partial files.
+//!
+//! A SCP file (the format of `MASTER.SCP`) is one callsign per line, which
+//! contest loggers use to suggest calls as you type. Lines starting with `#`
+//! are comments.
+
+use std::collections::BTreeSet;
+
+/// Render `callsigns` as an SCP file: upper-cased, de-duplicated and
+/// sorted, after `header` as comment lines.
+pub fn generate<'a>(header: &[String], callsigns: impl IntoIterator<Item = &'a str>) -> String {
+    let calls: BTreeSet<String> = callsigns
+        .into_iter()
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .collect();
+
+    let mut output = String::new();
+    for line in header {
+        output.push_str(&format!("# {}\n", line));
+    }
+    for call in calls {
+        output.push_str(&call);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let header = vec!["QRQ Crew and CWops members".to_string()];
+        let output = generate(&header, ["W6JSV", "k4mw", "W6JSV", "", "AA1A"]);
+        assert_eq!(output, "# QRQ Crew and CWops members\nAA1A\nK4MW\nW6JSV\n");
+        assert_eq!(generate(&[], ["K4MW"]), "K4MW\n");
+    }
+}
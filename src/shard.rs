@@ -0,0 +1,203 @@
+//! Splitting an output into several files, for rosters too big for one.
+//!
+//! An output with `shard_by` is rendered once per shard, each shard holding
+//! the lines whose callsign falls in it (a former-call line goes by the
+//! former callsign), plus an index listing the shard files. The set of
+//! shards is fixed per mode, so a shard that empties out is rewritten
+//! (header only) rather than left stale, and a shard whose members didn't
+//! change renders byte-identical and drops out of the commit.
+
+use crate::csv_fetcher::Member;
+use crate::report;
+use anyhow::{Result, bail};
+
+const CONTINENTS: [&str; 7] = ["AF", "AN", "AS", "EU", "NA", "OC", "SA"];
+const OTHER: &str = "other";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardBy {
+    /// The call area digit, e.g. `1` for W1AW and `0` for 2E0ABC
+    CallArea,
+    /// The first character of the callsign
+    Prefix,
+    /// The DXCC continent (needs `dxcc_file`)
+    Continent,
+}
+
+impl ShardBy {
+    pub fn parse(shard_by: &str) -> Result<Self> {
+        Ok(match shard_by {
+            "call_area" => ShardBy::CallArea,
+            "prefix" => ShardBy::Prefix,
+            "continent" => ShardBy::Continent,
+            other => bail!(
+                "unknown shard_by {:?} (expected call_area, prefix or continent)",
+                other
+            ),
+        })
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            ShardBy::CallArea => "call area",
+            ShardBy::Prefix => "callsign prefix",
+            ShardBy::Continent => "continent",
+        }
+    }
+
+    /// Every shard name, in order, ending in "other".
+    pub fn shards(&self) -> Vec<String> {
+        let names: Vec<String> = match self {
+            ShardBy::CallArea => ('0'..='9').map(String::from).collect(),
+            ShardBy::Prefix => ('0'..='9').chain('A'..='Z').map(String::from).collect(),
+            ShardBy::Continent => CONTINENTS.iter().map(|c| c.to_string()).collect(),
+        };
+        names.into_iter().chain([OTHER.to_string()]).collect()
+    }
+
+    /// The shard a line for `callsign` belongs in. `member` is who the line
+    /// is for; continents come from their DXCC entity.
+    pub fn shard(&self, callsign: &str, member: &Member) -> String {
+        let call = base_call(callsign).to_ascii_uppercase();
+        let shard = match self {
+            // The first digit after the first character, so a prefix that
+            // starts with a digit (2E0, 4X1) still finds its area
+            ShardBy::CallArea => call.chars().skip(1).find(|c| c.is_ascii_digit()),
+            ShardBy::Prefix => call.chars().next().filter(|c| c.is_ascii_alphanumeric()),
+            ShardBy::Continent => {
+                return member
+                    .dxcc
+                    .as_ref()
+                    .map(|d| d.continent.to_ascii_uppercase())
+                    .filter(|c| CONTINENTS.contains(&c.as_str()))
+                    .unwrap_or_else(|| OTHER.to_string());
+            }
+        };
+        shard.map(String::from).unwrap_or_else(|| OTHER.to_string())
+    }
+}
+
+/// The home call in a portable callsign: the longest `/` part.
+fn base_call(callsign: &str) -> &str {
+    callsign
+        .split('/')
+        .reduce(|a, b| if b.len() > a.len() { b } else { a })
+        .unwrap_or(callsign)
+}
+
+/// How an output is split: by what, where the shards go and where the
+/// index goes.
+#[derive(Debug, Clone)]
+pub struct Sharding {
+    by: ShardBy,
+    /// Shard path, with `{shard}` for the shard name
+    path: String,
+    index: String,
+}
+
+impl Sharding {
+    pub fn new(shard_by: &str, path: &str, index: Option<&str>) -> Result<Self> {
+        let by = ShardBy::parse(shard_by)?;
+        if !path.contains("{shard}") {
+            bail!("sharded path {:?} needs a {{shard}} placeholder", path);
+        }
+        let Some(index) = index else {
+            bail!("shard_by needs an index path");
+        };
+        Ok(Self {
+            by,
+            path: path.to_string(),
+            index: index.to_string(),
+        })
+    }
+
+    pub fn index_path(&self) -> &str {
+        &self.index
+    }
+
+    /// `(shard, path)` for every shard, in shard order.
+    pub fn files(&self) -> Vec<(String, String)> {
+        self.by
+            .shards()
+            .into_iter()
+            .map(|shard| {
+                let path = self.path.replace("{shard}", &shard);
+                (shard, path)
+            })
+            .collect()
+    }
+
+    /// Every shard's path.
+    pub fn paths(&self) -> Vec<String> {
+        self.files().into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// Whether a line for `callsign` (about `member`) belongs in `shard`.
+    pub fn contains(&self, shard: &str, callsign: &str, member: &Member) -> bool {
+        self.by.shard(callsign, member) == shard
+    }
+
+    /// The index file listing `shards` as `(path, member count)`. CSV for a
+    /// `.csv` index, Markdown otherwise.
+    pub fn index(&self, label: &str, shards: &[(String, usize)]) -> Result<String> {
+        let names = self.by.shards();
+        let rows = names
+            .iter()
+            .zip(shards)
+            .map(|(name, (path, count))| vec![name.clone(), path.clone(), count.to_string()])
+            .collect();
+        report::render_table(
+            &self.index,
+            &format!("{} shards", label),
+            &format!(
+                "The {} output is split by {}. Load every file listed.",
+                label,
+                self.by.describe()
+            ),
+            &[("shard", "Shard"), ("path", "File"), ("members", "Members")],
+            rows,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(callsign: &str) -> Member {
+        Member::new(callsign.to_string(), "1".to_string())
+    }
+
+    #[test]
+    fn test_shard_keys() {
+        let area = |call: &str| ShardBy::CallArea.shard(call, &member(call));
+        assert_eq!(area("W1AW"), "1");
+        assert_eq!(area("2E0ABC"), "0");
+        assert_eq!(area("KH6/W1AW"), "1");
+        assert_eq!(area("WAAA"), "other");
+        assert_eq!(ShardBy::Prefix.shard("k4mw", &member("k4mw")), "K");
+        assert_eq!(ShardBy::Continent.shard("W1AW", &member("W1AW")), "other");
+        assert!(ShardBy::parse("state").is_err());
+    }
+
+    #[test]
+    fn test_files_and_index() {
+        let sharding = Sharding::new("call_area", "skcc-{shard}.txt", Some("skcc.csv")).unwrap();
+        let files = sharding.files();
+        assert_eq!(files.len(), 11);
+        assert_eq!(files[1], ("1".to_string(), "skcc-1.txt".to_string()));
+        assert!(sharding.contains("1", "W1AW", &member("W1AW")));
+        assert!(!sharding.contains("4", "W1AW", &member("W1AW")));
+
+        let listed: Vec<(String, usize)> = files
+            .into_iter()
+            .map(|(shard, path)| (path, if shard == "1" { 2 } else { 0 }))
+            .collect();
+        let index = sharding.index("SKCC", &listed).unwrap();
+        assert!(index.starts_with("shard,path,members\n0,skcc-0.txt,0\n1,skcc-1.txt,2\n"));
+        assert!(index.ends_with("other,skcc-other.txt,0\n"));
+
+        assert!(Sharding::new("prefix", "skcc.txt", Some("index.md")).is_err());
+        assert!(Sharding::new("prefix", "skcc-{shard}.txt", None).is_err());
+    }
+}
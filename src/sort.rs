@@ -0,0 +1,220 @@
+//! Sort orders for generated files.
+//!
+//! Per-org `sort_by` values:
+//!
+//! - `callsign` (default): plain string order, so `W10AB` comes before
+//!   `W1ABC`
+//! - `callsign_natural`: digit runs compare as numbers, so `W1ABC`, `W2ABC`,
+//!   `W10AB`
+//! - `member_id`: by the number in the member id (`678C` is 678), members
+//!   without one last
+//! - `join_date`: by the date in `join_date_column` (`YYYY-MM-DD`,
+//!   `MM/DD/YYYY` or `DD.MM.YYYY`), members without one last
+//! - `roster`: the order of the roster itself
+//!
+//! Ties fall back to the callsign, so output never depends on input order
+//! (except for `roster`, where input order is the point).
+
+use crate::csv_fetcher::Member;
+use anyhow::{Result, bail};
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Callsign,
+    CallsignNatural,
+    MemberId,
+    /// By the date in this roster column
+    JoinDate(String),
+    Roster,
+}
+
+/// What a line is sorted by: its member, that member's position in the
+/// roster, and the callsign the line is for (a former callsign, for
+/// former-call lines).
+#[derive(Debug, Clone, Copy)]
+pub struct SortKey<'a> {
+    pub index: usize,
+    pub member: &'a Member,
+    pub callsign: &'a str,
+}
+
+impl SortOrder {
+    pub fn parse(sort_by: &str, join_date_column: Option<&str>) -> Result<Self> {
+        Ok(match sort_by {
+            "callsign" => SortOrder::Callsign,
+            "callsign_natural" => SortOrder::CallsignNatural,
+            "member_id" => SortOrder::MemberId,
+            "join_date" => match join_date_column {
+                Some(column) => SortOrder::JoinDate(column.to_string()),
+                None => bail!("sort_by = \"join_date\" needs join_date_column"),
+            },
+            "roster" => SortOrder::Roster,
+            other => bail!(
+                "unknown sort_by {:?} (expected callsign, callsign_natural, member_id, join_date or roster)",
+                other
+            ),
+        })
+    }
+
+    /// The roster column this order reads, if any.
+    pub fn roster_field(&self) -> Option<&str> {
+        match self {
+            SortOrder::JoinDate(column) => Some(column),
+            _ => None,
+        }
+    }
+
+    pub fn compare(&self, a: SortKey, b: SortKey) -> Ordering {
+        let primary = match self {
+            SortOrder::Callsign => Ordering::Equal,
+            SortOrder::CallsignNatural => natural_cmp(a.callsign, b.callsign),
+            SortOrder::MemberId => last_if_none(member_number(a.member), member_number(b.member))
+                .then_with(|| a.member.member_id.cmp(&b.member.member_id)),
+            SortOrder::JoinDate(column) => {
+                last_if_none(join_date(a.member, column), join_date(b.member, column))
+            }
+            SortOrder::Roster => a.index.cmp(&b.index),
+        };
+        primary.then_with(|| a.callsign.cmp(b.callsign))
+    }
+
+    /// `members` in this order.
+    pub fn sorted<'a>(&self, members: &'a [Member]) -> Vec<&'a Member> {
+        let mut keyed: Vec<SortKey> = members
+            .iter()
+            .enumerate()
+            .map(|(index, member)| SortKey {
+                index,
+                member,
+                callsign: &member.callsign,
+            })
+            .collect();
+        keyed.sort_by(|a, b| self.compare(*a, *b));
+        keyed.into_iter().map(|k| k.member).collect()
+    }
+}
+
+/// `Some` before `None`, then ascending.
+fn last_if_none<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// The first run of digits in the member id.
+fn member_number(member: &Member) -> Option<u64> {
+    let digits: String = member
+        .member_id
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn join_date(member: &Member, column: &str) -> Option<NaiveDate> {
+    let value = member.fields.get(column)?.trim();
+    ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+}
+
+/// Compare with runs of digits compared as numbers.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn take_number(chars: &mut Peekable<Chars>) -> String {
+        let mut digits = String::new();
+        while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            digits.push(*c);
+            chars.next();
+        }
+        digits
+    }
+
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (take_number(&mut a), take_number(&mut b));
+                let (x_trimmed, y_trimmed) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let ordering = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calls(order: &SortOrder, members: &[Member]) -> Vec<String> {
+        order
+            .sorted(members)
+            .iter()
+            .map(|m| m.callsign.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_orders() {
+        let mut members: Vec<Member> = [("W10AB", "678C"), ("W1ABC", "12"), ("K4MW", "x")]
+            .iter()
+            .map(|(c, id)| Member::new(c.to_string(), id.to_string()))
+            .collect();
+        members[0]
+            .fields
+            .insert("Joined".to_string(), "03/01/2020".to_string());
+        members[1]
+            .fields
+            .insert("Joined".to_string(), "2021-06-15".to_string());
+
+        assert_eq!(
+            calls(&SortOrder::Callsign, &members),
+            ["K4MW", "W10AB", "W1ABC"]
+        );
+        assert_eq!(
+            calls(&SortOrder::CallsignNatural, &members),
+            ["K4MW", "W1ABC", "W10AB"]
+        );
+        assert_eq!(
+            calls(&SortOrder::MemberId, &members),
+            ["W1ABC", "W10AB", "K4MW"]
+        );
+        let join = SortOrder::parse("join_date", Some("Joined")).unwrap();
+        assert_eq!(calls(&join, &members), ["W10AB", "W1ABC", "K4MW"]);
+        assert_eq!(
+            calls(&SortOrder::Roster, &members),
+            ["W10AB", "W1ABC", "K4MW"]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(SortOrder::parse("join_date", None).is_err());
+        assert!(SortOrder::parse("name", None).is_err());
+        assert_eq!(SortOrder::parse("roster", None).unwrap(), SortOrder::Roster);
+    }
+}
@@ -0,0 +1,379 @@
+//! Note line templates.
+//!
+//! A template is literal text with `{placeholder}`s, e.g.
+//! `{callsign} {emoji} [{nickname} ]{label} #{member_id}`. Text in square
+//! brackets is a conditional section: it's only written when every
+//! placeholder inside it has a value. `{{`, `}}`, `[[` and `]]` are literal
+//! braces and brackets.
+//!
+//! Placeholders:
+//!
+//! - `{callsign}`, `{roster_callsign}`, `{member_id}`, `{nickname}`
+//! - `{emoji}`, `{label}` from the org
+//! - `{flag}` (with `flag_emoji`), `{license}` (marker and problem, with
+//!   `license_policy = "flag"`)
+//! - `{dxcc}`, `{continent}`, `{cq_zone}`, `{itu_zone}`, `{country}` from the
+//!   DXCC country file, `{license_expires}` from the license lookups
+//! - `{field:Header}` for any other roster column, by header name
+//!
+//! Templates are parsed when the config is loaded, so a typo in a
+//! placeholder fails at startup rather than producing broken notes.
+//!
+//! Header lines use the same `{placeholder}` syntax (without conditional
+//! sections) for run metadata: see [`HEADER_PLACEHOLDERS`].
+
+use crate::csv_fetcher::Member;
+use anyhow::{Result, bail};
+
+/// The line every org used before templates were configurable.
+pub const DEFAULT_LINE_TEMPLATE: &str =
+    "{callsign} [{flag} ]{emoji} [{nickname} ]{label} #{member_id}[ {license}]";
+
+/// Placeholders available in header text.
+pub const HEADER_PLACEHOLDERS: &[&str] = &[
+    "label",
+    "org",
+    "member_count",
+    "source",
+    "source_type",
+    "generated",
+];
+
+/// Check that header text only uses known placeholders.
+pub fn validate_header(text: &str) -> Result<()> {
+    let mut result = Ok(());
+    expand_header_with(text, |name| {
+        if !HEADER_PLACEHOLDERS.contains(&name) && result.is_ok() {
+            result = Err(anyhow::anyhow!("unknown placeholder {{{}}}", name));
+        }
+        None
+    })?;
+    result
+}
+
+/// Fill in header text. Placeholders `value` doesn't know are left as is.
+pub fn expand_header(text: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    expand_header_with(text, value).unwrap_or_else(|_| text.to_string())
+}
+
+fn expand_header_with(text: &str, mut value: impl FnMut(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek() == Some(&c) => {
+                chars.next();
+                out.push(c);
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => bail!("unclosed placeholder {{{}", name),
+                    }
+                }
+                match value(name.trim()) {
+                    Some(v) => out.push_str(&v),
+                    None => out.push_str(&format!("{{{}}}", name)),
+                }
+            }
+            '}' => bail!("unmatched '}}' (write '}}}}' for a literal brace)"),
+            _ => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// A value a template can refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placeholder {
+    Callsign,
+    RosterCallsign,
+    MemberId,
+    Nickname,
+    Emoji,
+    Label,
+    Flag,
+    License,
+    LicenseExpires,
+    Dxcc,
+    Continent,
+    CqZone,
+    ItuZone,
+    Country,
+    /// Another roster column, by header name.
+    Field(String),
+}
+
+impl Placeholder {
+    /// The placeholder with this name (without braces), if any.
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(column) = name.strip_prefix("field:") {
+            let column = column.trim();
+            return (!column.is_empty()).then(|| Placeholder::Field(column.to_string()));
+        }
+        Some(match name {
+            "callsign" => Placeholder::Callsign,
+            "roster_callsign" => Placeholder::RosterCallsign,
+            "member_id" => Placeholder::MemberId,
+            "nickname" => Placeholder::Nickname,
+            "emoji" => Placeholder::Emoji,
+            "label" => Placeholder::Label,
+            "flag" => Placeholder::Flag,
+            "license" => Placeholder::License,
+            "license_expires" => Placeholder::LicenseExpires,
+            "dxcc" => Placeholder::Dxcc,
+            "continent" => Placeholder::Continent,
+            "cq_zone" => Placeholder::CqZone,
+            "itu_zone" => Placeholder::ItuZone,
+            "country" => Placeholder::Country,
+            _ => return None,
+        })
+    }
+}
+
+/// A member's value for `placeholder`. The org-level `{emoji}` and
+/// `{label}` are `None`, and `{license}` is just the problem, if any.
+pub fn member_value(member: &Member, placeholder: &Placeholder) -> Option<String> {
+    let dxcc = member.dxcc.as_ref();
+    match placeholder {
+        Placeholder::Callsign => Some(member.callsign.clone()),
+        Placeholder::RosterCallsign => Some(member.roster_callsign.clone()),
+        Placeholder::MemberId => Some(member.member_id.clone()),
+        Placeholder::Nickname => member.nickname.clone(),
+        Placeholder::Emoji | Placeholder::Label => None,
+        Placeholder::Flag => dxcc.and_then(|d| d.flag()),
+        Placeholder::License => member.license_problem.map(|p| p.to_string()),
+        Placeholder::LicenseExpires => member
+            .license
+            .as_ref()
+            .and_then(|l| l.expires)
+            .map(|d| d.to_string()),
+        Placeholder::Dxcc => dxcc.map(|d| d.name.clone()),
+        Placeholder::Continent => dxcc.map(|d| d.continent.clone()),
+        Placeholder::CqZone => dxcc.map(|d| d.cq_zone.to_string()),
+        Placeholder::ItuZone => dxcc.map(|d| d.itu_zone.to_string()),
+        Placeholder::Country => dxcc.and_then(|d| d.iso_country.clone()),
+        Placeholder::Field(name) => member.fields.get(name).cloned(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Value(Placeholder),
+    /// Written only when all of its placeholders have values.
+    Optional(Vec<Segment>),
+}
+
+/// A parsed note line template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineTemplate {
+    segments: Vec<Segment>,
+}
+
+impl LineTemplate {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        // Segments of the conditional section being parsed, if any.
+        let mut optional: Option<Vec<Segment>> = None;
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        fn flush(literal: &mut String, out: &mut Vec<Segment>) {
+            if !literal.is_empty() {
+                out.push(Segment::Literal(std::mem::take(literal)));
+            }
+        }
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' | '}' | '[' | ']' if chars.peek() == Some(&c) => {
+                    chars.next();
+                    literal.push(c);
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => bail!("unclosed placeholder {{{}", name),
+                        }
+                    }
+                    let Some(placeholder) = Placeholder::parse(name.trim()) else {
+                        bail!("unknown placeholder {{{}}}", name);
+                    };
+                    let out = optional.as_mut().unwrap_or(&mut segments);
+                    flush(&mut literal, out);
+                    out.push(Segment::Value(placeholder));
+                }
+                '}' => bail!("unmatched '}}' (write '}}}}' for a literal brace)"),
+                '[' => {
+                    if optional.is_some() {
+                        bail!("conditional sections can't be nested");
+                    }
+                    flush(&mut literal, &mut segments);
+                    optional = Some(Vec::new());
+                }
+                ']' => {
+                    let Some(mut inner) = optional.take() else {
+                        bail!("unmatched ']' (write ']]' for a literal bracket)");
+                    };
+                    flush(&mut literal, &mut inner);
+                    if !inner.iter().any(|s| matches!(s, Segment::Value(_))) {
+                        bail!("conditional section has no placeholder");
+                    }
+                    segments.push(Segment::Optional(inner));
+                }
+                _ => literal.push(c),
+            }
+        }
+        if optional.is_some() {
+            bail!("unclosed conditional section '['");
+        }
+        flush(&mut literal, &mut segments);
+
+        if !segments
+            .iter()
+            .any(|s| matches!(s, Segment::Value(Placeholder::Callsign)))
+        {
+            bail!("template must include {{callsign}} outside a conditional section");
+        }
+        Ok(Self { segments })
+    }
+
+    /// Roster columns the template refers to with `{field:...}`.
+    pub fn roster_fields(&self) -> Vec<String> {
+        fn collect(segments: &[Segment], out: &mut Vec<String>) {
+            for segment in segments {
+                match segment {
+                    Segment::Value(Placeholder::Field(name)) if !out.contains(name) => {
+                        out.push(name.clone())
+                    }
+                    Segment::Optional(inner) => collect(inner, out),
+                    _ => {}
+                }
+            }
+        }
+        let mut fields = Vec::new();
+        collect(&self.segments, &mut fields);
+        fields
+    }
+
+    /// Fill in the template. `value` returns `None` (or an empty string) for
+    /// placeholders without a value; outside conditional sections those are
+    /// written as nothing.
+    pub fn render(&self, value: impl Fn(&Placeholder) -> Option<String>) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Value(placeholder) => {
+                    out.push_str(&value(placeholder).unwrap_or_default());
+                }
+                Segment::Optional(inner) => {
+                    let mut section = String::new();
+                    let complete = inner.iter().all(|segment| match segment {
+                        Segment::Literal(text) => {
+                            section.push_str(text);
+                            true
+                        }
+                        Segment::Value(placeholder) => match value(placeholder) {
+                            Some(v) if !v.is_empty() => {
+                                section.push_str(&v);
+                                true
+                            }
+                            _ => false,
+                        },
+                        Segment::Optional(_) => unreachable!("sections don't nest"),
+                    });
+                    if complete {
+                        out.push_str(&section);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+impl Default for LineTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_LINE_TEMPLATE).expect("default template is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_with_conditional_sections() {
+        let template =
+            LineTemplate::parse("{callsign} [{nickname} ]#{member_id}[ ({field:State})]").unwrap();
+        assert_eq!(template.roster_fields(), ["State"]);
+
+        let line = template.render(|p| match p {
+            Placeholder::Callsign => Some("K4MW".to_string()),
+            Placeholder::MemberId => Some("1".to_string()),
+            Placeholder::Nickname => Some("Mike".to_string()),
+            Placeholder::Field(_) => Some("TN".to_string()),
+            _ => None,
+        });
+        assert_eq!(line, "K4MW Mike #1 (TN)");
+
+        let line = template.render(|p| match p {
+            Placeholder::Callsign => Some("K4MW".to_string()),
+            Placeholder::MemberId => Some("1".to_string()),
+            Placeholder::Nickname => Some(String::new()),
+            _ => None,
+        });
+        assert_eq!(line, "K4MW #1");
+
+        let literal = LineTemplate::parse("{callsign} {{x}} [[y]]").unwrap();
+        assert_eq!(literal.render(|_| Some("W1AW".to_string())), "W1AW {x} [y]");
+    }
+
+    #[test]
+    fn test_header_placeholders() {
+        assert!(validate_header("{member_count} members from {source}").is_ok());
+        assert!(
+            validate_header("{members}")
+                .unwrap_err()
+                .to_string()
+                .contains("unknown placeholder {members}")
+        );
+        assert!(validate_header("{label").is_err());
+
+        let text = expand_header(
+            "{label}: {member_count} members {{live}}",
+            |name| match name {
+                "label" => Some("CWops".to_string()),
+                "member_count" => Some("42".to_string()),
+                _ => None,
+            },
+        );
+        assert_eq!(text, "CWops: 42 members {live}");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (template, error) in [
+            ("{callsign} {nick}", "unknown placeholder {nick}"),
+            ("{callsign} {label", "unclosed placeholder"),
+            ("{callsign} [{nickname}", "unclosed conditional"),
+            ("{callsign} [x [{nickname}]]", "can't be nested"),
+            ("{callsign} [no value]", "has no placeholder"),
+            ("{callsign} ]", "unmatched ']'"),
+            ("{nickname} {label}", "must include {callsign}"),
+            ("{callsign} {field:}", "unknown placeholder"),
+        ] {
+            let err = LineTemplate::parse(template).unwrap_err().to_string();
+            assert!(err.contains(error), "{template}: {err}");
+        }
+        assert!(LineTemplate::parse(DEFAULT_LINE_TEMPLATE).is_ok());
+    }
+}
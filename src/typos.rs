@@ -0,0 +1,245 @@
+//! Typo suggestions for roster callsigns QRZ doesn't know.
+//!
+//! A not-found callsign is usually a typo in the roster sheet (W1ABX for
+//! W1ABC). The lookup cache holds every callsign QRZ has confirmed, across
+//! all orgs, so it doubles as a local callbook to search: any known call
+//! one edit away (a substitution, insertion, deletion or swap of adjacent
+//! characters) is a candidate, and so is one two edits away when its name
+//! matches the member's roster name.
+
+use crate::csv_fetcher::Member;
+use crate::names::{clean_name, same_person};
+use crate::nickname_cache::{CachedLookup, NicknameCache};
+use crate::report::render_table;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+/// At most this many suggestions are listed per callsign.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A known callsign that might be what the roster meant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub callsign: String,
+    pub name: Option<String>,
+    /// The known call's name matches the member's roster name.
+    pub name_matches: bool,
+    pub distance: usize,
+}
+
+/// A roster callsign QRZ reported as not found, with its suggestions.
+#[derive(Debug, Clone)]
+pub struct NotFoundCall {
+    pub member_id: String,
+    pub callsign: String,
+    pub name: Option<String>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Callsigns QRZ has confirmed, with the operator's cleaned first name.
+pub struct KnownCalls {
+    calls: HashMap<String, Option<String>>,
+}
+
+impl KnownCalls {
+    /// Every callsign the cache has a QRZ record for: current callsigns,
+    /// and queried callsigns that were still current.
+    pub fn from_cache(cache: &NicknameCache) -> Self {
+        let mut calls = HashMap::new();
+        for record in cache.records() {
+            if let CachedLookup::Found {
+                current_call,
+                nickname,
+            } = record.hit.lookup
+            {
+                let name = nickname.as_deref().and_then(clean_name);
+                if current_call.eq_ignore_ascii_case(&record.callsign) {
+                    calls.insert(record.callsign.clone(), name.clone());
+                }
+                calls.entry(current_call).or_insert(name);
+            }
+        }
+        Self { calls }
+    }
+
+    /// Likely corrections for `callsign`, best first. Callsigns in
+    /// `exclude` (other members of the same roster) are skipped.
+    pub fn suggest(
+        &self,
+        callsign: &str,
+        roster_name: Option<&str>,
+        exclude: &HashSet<&str>,
+    ) -> Vec<Suggestion> {
+        let mut suggestions: Vec<Suggestion> = self
+            .calls
+            .iter()
+            .filter(|(call, _)| !exclude.contains(call.as_str()) && call.as_str() != callsign)
+            .filter_map(|(call, name)| {
+                let distance = edit_distance(callsign, call);
+                let name_matches = match (roster_name, name) {
+                    (Some(roster), Some(known)) => same_person(roster, known),
+                    _ => false,
+                };
+                let plausible = distance == 1 || (distance == 2 && name_matches);
+                plausible.then(|| Suggestion {
+                    callsign: call.clone(),
+                    name: name.clone(),
+                    name_matches,
+                    distance,
+                })
+            })
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.name_matches
+                .cmp(&a.name_matches)
+                .then(a.distance.cmp(&b.distance))
+                .then_with(|| a.callsign.cmp(&b.callsign))
+        });
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+}
+
+/// Members whose roster callsign QRZ reported as not found, with
+/// suggestions from `known`. Sorted by callsign.
+pub fn check(members: &[Member], cache: &NicknameCache, known: &KnownCalls) -> Vec<NotFoundCall> {
+    let roster: HashSet<&str> = members
+        .iter()
+        .flat_map(|m| [m.callsign.as_str(), m.roster_callsign.as_str()])
+        .collect();
+    let mut not_found: Vec<NotFoundCall> = members
+        .iter()
+        .filter(|m| {
+            cache
+                .lookup(&m.roster_callsign)
+                .is_some_and(|hit| hit.lookup == CachedLookup::NotFound)
+        })
+        .map(|m| NotFoundCall {
+            member_id: m.member_id.clone(),
+            callsign: m.roster_callsign.clone(),
+            name: m.roster_nickname.clone(),
+            suggestions: known.suggest(&m.roster_callsign, m.roster_nickname.as_deref(), &roster),
+        })
+        .collect();
+    not_found.sort_by(|a, b| a.callsign.cmp(&b.callsign));
+    not_found
+}
+
+/// Render not-found callsigns as CSV (for `.csv` paths) or Markdown.
+pub fn report(label: &str, path: &str, not_found: &[NotFoundCall]) -> Result<String> {
+    let rows = not_found
+        .iter()
+        .map(|n| {
+            let suggestions = n
+                .suggestions
+                .iter()
+                .map(|s| match (&s.name, s.name_matches) {
+                    (Some(name), true) => format!("{} ({}, name matches)", s.callsign, name),
+                    (Some(name), false) => format!("{} ({})", s.callsign, name),
+                    (None, _) => s.callsign.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            vec![
+                n.member_id.clone(),
+                n.callsign.clone(),
+                n.name.clone().unwrap_or_default(),
+                suggestions,
+            ]
+        })
+        .collect();
+    render_table(
+        path,
+        &format!("{} Callsigns Not Found", label),
+        "Roster callsigns QRZ has no record of, with known callsigns that are a likely \
+         typo away. Suggestions whose name matches the roster name are listed first.",
+        &[
+            ("member_id", "Member"),
+            ("callsign", "Roster call"),
+            ("name", "Roster name"),
+            ("suggestions", "Did you mean"),
+        ],
+        rows,
+    )
+}
+
+/// Edit distance counting substitutions, insertions, deletions and swaps of
+/// adjacent characters (optimal string alignment).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nickname_cache::CacheTtl;
+    use crate::qrz::QrzInfo;
+
+    fn found(cache: &mut NicknameCache, call: &str, name: &str) {
+        cache.insert_found(
+            call,
+            &QrzInfo {
+                current_call: call.to_string(),
+                nickname: Some(name.to_string()),
+                license_expires: None,
+            },
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("W1ABC", "W1ABC"), 0);
+        assert_eq!(edit_distance("W1ABX", "W1ABC"), 1);
+        assert_eq!(edit_distance("W1BAC", "W1ABC"), 1);
+        assert_eq!(edit_distance("W1AB", "W1ABC"), 1);
+        assert_eq!(edit_distance("K1ABX", "W1ABC"), 2);
+    }
+
+    #[test]
+    fn test_check_suggests_known_calls() {
+        let mut cache = NicknameCache::in_memory(CacheTtl::default());
+        found(&mut cache, "W1ABC", "Bob");
+        found(&mut cache, "W1ABD", "Alice");
+        found(&mut cache, "K1ABD", "Robert");
+        found(&mut cache, "K4MW", "Mike");
+        cache.insert_not_found("W1ABX");
+
+        let mut typo = Member::new("W1ABX".to_string(), "12".to_string());
+        typo.roster_nickname = Some("Bob".to_string());
+        let members = vec![typo, Member::new("K4MW".to_string(), "1".to_string())];
+
+        let known = KnownCalls::from_cache(&cache);
+        let not_found = check(&members, &cache, &known);
+        assert_eq!(not_found.len(), 1);
+        let calls: Vec<&str> = not_found[0]
+            .suggestions
+            .iter()
+            .map(|s| s.callsign.as_str())
+            .collect();
+        // Name matches first (including the two-edit K1ABD), then the rest.
+        assert_eq!(calls, ["W1ABC", "K1ABD", "W1ABD"]);
+
+        let md = report("CWops", "typos.md", &not_found).unwrap();
+        assert!(md.contains("| 12 | W1ABX | Bob | W1ABC (Bob, name matches); K1ABD"));
+    }
+}